@@ -1,20 +1,213 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use indctive::dct_map::{DctMap, FooterEntry, FooterSubEntry};
+use anyhow::anyhow;
+use indctive::dct_map::{
+  capacity_for, DctMap, FooterEntry, FooterSubEntry, DEFAULT_MAX_LOAD_FACTOR,
+};
+use indctive::encoding::{CustomEncoding, TextEncoding};
+use indctive::hasher::{hasher_by_name, DctHasher, HASHER_NAMES};
+use indctive::key_recovery::KeyRecovery;
+use indexmap::IndexMap;
+use matryoshka::{ContainerData, Data};
+
+use crate::dct::exchange::{ExchangeFormat, Languages, Unit};
+use crate::dct::lint::LintAction;
+use crate::dct::workspace::{PackAllAction, UnpackAllAction};
+use crate::oct::{collect_files, Endian};
+
+mod exchange;
+mod lint;
+mod workspace;
+
+/// Prefix of keys that are only known by their line id, like `#0x1a2b3c4d`.
+const HASH_KEY_PREFIX: &str = "#0x";
+
+fn hash_key(line_id: u32) -> String {
+  format!("{HASH_KEY_PREFIX}{line_id:08x}")
+}
+
+fn parse_hash_key(key: &str) -> Option<u32> {
+  u32::from_str_radix(key.strip_prefix(HASH_KEY_PREFIX)?, 16).ok()
+}
+
+fn parse_load_factor(value: &str) -> Result<f32, String> {
+  let load_factor: f32 = value.parse().map_err(|err| format!("{err}"))?;
+  if load_factor > 0.0 && load_factor <= 1.0 {
+    Ok(load_factor)
+  } else {
+    Err("Load factor has to be above 0 and at most 1".to_string())
+  }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct SerdeMetadata {
+  /// Missing from metadata unpacked by older versions, which only read little endian
+  #[serde(default)]
+  pub endian: Option<Endian>,
+  /// Missing from metadata unpacked by older versions, which only hashed with lookup2
+  #[serde(default = "default_hasher_name")]
+  pub hasher: String,
   pub initial_hash_value: u32,
   pub footer_entries: Vec<SerdeFooterEntry>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub layout: Option<SerdeLayout>,
+  #[serde(default, skip_serializing_if = "SerdeEncoding::is_utf8")]
+  pub encoding: SerdeEncoding,
+}
+
+fn default_hasher_name() -> String {
+  "lookup2".to_string()
+}
+
+fn hasher(name: &str) -> anyhow::Result<Arc<dyn DctHasher>> {
+  hasher_by_name(name).ok_or_else(|| {
+    anyhow!(
+      "Unknown hasher {name}, expected one of {}",
+      HASHER_NAMES.join(", ")
+    )
+  })
+}
+
+/// How the strings of a dct are stored, either `utf-8`, `windows-1250` to `windows-1258`, or a
+/// table of the bytes that aren't ASCII, like `{"0x80": "€"}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+enum SerdeEncoding {
+  Label(String),
+  Table(BTreeMap<String, char>),
+}
+
+impl Default for SerdeEncoding {
+  fn default() -> Self {
+    SerdeEncoding::from(&TextEncoding::Utf8)
+  }
+}
+
+impl SerdeEncoding {
+  /// Parses the `--encoding` option, a label or the path to a JSON file with a table.
+  fn parse(value: &str) -> anyhow::Result<Self> {
+    if let Ok(encoding) = TextEncoding::from_label(value) {
+      return Ok(SerdeEncoding::from(&encoding));
+    }
+
+    let path = Path::new(value);
+    if !path.is_file() {
+      return Err(anyhow!(
+        "{value} is neither utf-8, windows-1250 to windows-1258, nor a JSON file with a table"
+      ));
+    }
+
+    let table = serde_json::from_reader(BufReader::new(File::open(path)?))
+      .map_err(|err| anyhow!("{value}: {err}"))?;
+    let encoding = SerdeEncoding::Table(table);
+    TextEncoding::try_from(&encoding)?;
+
+    Ok(encoding)
+  }
+
+  fn is_utf8(&self) -> bool {
+    matches!(TextEncoding::try_from(self), Ok(TextEncoding::Utf8))
+  }
+}
+
+impl From<&TextEncoding> for SerdeEncoding {
+  fn from(value: &TextEncoding) -> Self {
+    match value {
+      TextEncoding::Custom(custom) => SerdeEncoding::Table(
+        custom
+          .mappings()
+          .map(|(byte, c)| (format!("{byte:#04x}"), c))
+          .collect(),
+      ),
+      encoding => SerdeEncoding::Label(encoding.to_string()),
+    }
+  }
 }
 
+impl TryFrom<&SerdeEncoding> for TextEncoding {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &SerdeEncoding) -> Result<Self, Self::Error> {
+    match value {
+      SerdeEncoding::Label(label) => Ok(TextEncoding::from_label(label)?),
+      SerdeEncoding::Table(table) => {
+        let mappings = table
+          .iter()
+          .map(|(byte, c)| {
+            let parsed = byte
+              .strip_prefix("0x")
+              .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+              .filter(|byte| *byte != 0);
+            match parsed {
+              Some(byte) => Ok((byte, *c)),
+              None => Err(anyhow!(
+                "Encoding table key {byte:?} isn't a byte from 0x01 to 0xff"
+              )),
+            }
+          })
+          .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(TextEncoding::Custom(CustomEncoding::new(mappings)))
+      }
+    }
+  }
+}
+
+/// Slots of an unpacked dct, which pack puts every line back into while the keys are unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SerdeLayout {
+  pub capacity: u32,
+  /// Line ids in [`DctMap::probe_order`]
+  pub lines: Vec<u32>,
+  /// Line ids of the stringids keys in [`DctMap::probe_order`], if it has the same capacity
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub stringids: Vec<u32>,
+}
+
+impl From<DctMap> for SerdeMetadata {
+  fn from(value: DctMap) -> Self {
+    let layout = SerdeLayout {
+      capacity: value.get_max_capacity(),
+      lines: value.probe_order(),
+      stringids: Vec::new(),
+    };
+
+    Self {
+      endian: Some(value.get_endian().into()),
+      hasher: value.get_hasher().name().to_string(),
+      encoding: SerdeEncoding::from(value.get_encoding()),
+      initial_hash_value: value.get_initial_hash_value(),
+      footer_entries: value
+        .footer_entries
+        .into_iter()
+        .map(SerdeFooterEntry::from)
+        .collect(),
+      layout: Some(layout),
+    }
+  }
+}
+
+impl SerdeMetadata {
+  fn with_stringids(mut self, stringids_dct: &DctMap) -> Self {
+    if let Some(layout) = &mut self.layout {
+      if layout.capacity == stringids_dct.get_max_capacity() {
+        layout.stringids = stringids_dct.probe_order();
+      }
+    }
+    self
+  }
+}
+
+/// An entry of the footer, see [`FooterEntry`].
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct SerdeFooterEntry {
   pub text: String,
@@ -47,6 +240,7 @@ impl From<SerdeFooterEntry> for FooterEntry {
   }
 }
 
+/// A string of a footer entry and the number it is paired with.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct SerdeFooterSubEntry {
   pub text: String,
@@ -71,9 +265,37 @@ impl From<SerdeFooterSubEntry> for FooterSubEntry {
   }
 }
 
-fn read_dct_file<P: AsRef<Path>>(path: P) -> anyhow::Result<DctMap> {
-  let mut dct_file = File::open(path)?;
-  let dct_map = DctMap::from_reader(&mut dct_file)?;
+/// How the dcts an action reads are stored, if not the way their header tells.
+#[derive(Args, Debug)]
+struct DctReadOptions {
+  /// Reads the dcts in this endian instead of the one their header is written in
+  #[clap(short, long)]
+  endian: Option<Endian>,
+
+  /// Strings are stored in utf-8, windows-1250 to windows-1258, or the encoding of a JSON file
+  /// mapping bytes to characters, like `{"0x80": "€"}`
+  #[clap(long, value_parser = SerdeEncoding::parse)]
+  encoding: Option<SerdeEncoding>,
+
+  /// Hash of the keys, which dcts don't record
+  #[clap(long, value_parser = HASHER_NAMES)]
+  hasher: Option<String>,
+}
+
+fn read_dct_file<P: AsRef<Path>>(path: P, options: &DctReadOptions) -> anyhow::Result<DctMap> {
+  let encoding = match &options.encoding {
+    Some(encoding) => TextEncoding::try_from(encoding)?,
+    None => TextEncoding::Utf8,
+  };
+
+  let path = path.as_ref();
+  let mut dct_file = BufReader::new(File::open(path)?);
+  let endian = options.endian.clone().map(Endian::into);
+  let mut dct_map = DctMap::from_reader_with_options(&mut dct_file, endian, encoding)
+    .map_err(|err| anyhow!("{}: {err}", path.to_string_lossy()))?;
+  if let Some(hasher_name) = &options.hasher {
+    dct_map.set_hasher(hasher(hasher_name)?);
+  }
 
   Ok(dct_map)
 }
@@ -96,6 +318,18 @@ pub struct DctModule {
 pub(crate) enum Action {
   Unpack(UnpackAction),
   Pack(PackAction),
+  /// Unpacks a dct without its stringids file, by guessing the keys
+  Recover(RecoverAction),
+  /// Exports a language for translation tools, next to the lines of a source language
+  Export(ExportAction),
+  /// Packs the translations of a file written by export
+  Import(ImportAction),
+  /// Unpacks every language of a folder into one table
+  UnpackAll(UnpackAllAction),
+  /// Packs every language of a table written by unpack-all
+  PackAll(PackAllAction),
+  /// Checks an unpacked translation against its source language
+  Lint(LintAction),
 }
 
 impl DctModule {
@@ -103,6 +337,12 @@ impl DctModule {
     match &self.action {
       Action::Unpack(action) => action.execute(),
       Action::Pack(action) => action.execute(),
+      Action::Recover(action) => action.execute(),
+      Action::Export(action) => action.execute(),
+      Action::Import(action) => action.execute(),
+      Action::UnpackAll(action) => action.execute(),
+      Action::PackAll(action) => action.execute(),
+      Action::Lint(action) => action.execute(),
     }
   }
 }
@@ -112,57 +352,93 @@ pub(crate) struct UnpackAction {
   lang_dct: PathBuf,
   stringids_dct: PathBuf,
   output_path: PathBuf,
+
+  #[clap(flatten)]
+  read_options: DctReadOptions,
 }
 
 impl UnpackAction {
   pub fn execute(&self) -> anyhow::Result<()> {
-    let lang_dct = read_dct_file(&self.lang_dct)?;
-    let stringids_dct = read_dct_file(&self.stringids_dct)?;
+    let lang_dct = read_dct_file(&self.lang_dct, &self.read_options)?;
+    let stringids_dct = read_dct_file(&self.stringids_dct, &self.read_options)?;
 
-    let mut translation_map = HashMap::new();
-    for (_, translation_key) in stringids_dct.iter_line_entries() {
-      let translated_text = match lang_dct.get_line_entry(translation_key) {
-        Ok(text) => Value::String(text.to_string()),
-        Err(_) => Value::Null,
-      };
+    let translation_map = unpack_translations(&lang_dct, &stringids_dct);
 
-      translation_map.insert(translation_key.to_string(), translated_text);
+    let unknown = translation_map
+      .keys()
+      .filter(|key| parse_hash_key(key).is_some())
+      .count();
+    if unknown > 0 {
+      println!("Kept {unknown} lines without a key in stringids as {HASH_KEY_PREFIX}<line id>");
     }
 
-    if self.output_path.is_file() {
-      return Err(anyhow::Error::msg("Output path is a file."));
-    }
+    let metadata = SerdeMetadata::from(lang_dct).with_stringids(&stringids_dct);
+    write_unpacked(&self.output_path, &translation_map, &metadata)
+  }
+}
+
+/// Maps every key of `stringids_dct` to its text in `lang_dct`, or null if it isn't translated.
+///
+/// Lines missing from stringids are kept by their line id, pack writes them back under it.
+fn unpack_translations(lang_dct: &DctMap, stringids_dct: &DctMap) -> IndexMap<String, Value> {
+  let mut translation_map = IndexMap::new();
+  let mut known_line_ids = HashSet::new();
+  for (_, translation_key) in stringids_dct.iter_line_entries() {
+    let translated_text = match lang_dct.get_line_entry(translation_key) {
+      Ok(text) => Value::String(text.to_string()),
+      Err(_) => Value::Null,
+    };
 
-    if !self.output_path.exists() {
-      fs::create_dir(&self.output_path)?;
+    known_line_ids.insert(lang_dct.hash_key(translation_key));
+    translation_map.insert(translation_key.to_string(), translated_text);
+  }
+
+  for (line_id, text) in lang_dct.iter_line_entries() {
+    if !known_line_ids.contains(&line_id) {
+      translation_map.insert(hash_key(line_id), Value::String(text.to_string()));
     }
+  }
 
-    let initial_hash_value = lang_dct.get_initial_hash_value();
-    let footer_entries: Vec<SerdeFooterEntry> = lang_dct
-      .footer_entries
-      .into_iter()
-      .map(SerdeFooterEntry::from)
-      .collect();
+  translation_map
+}
 
-    let metadata = SerdeMetadata {
-      initial_hash_value,
-      footer_entries,
-    };
+fn write_unpacked(
+  output_path: &Path,
+  translation_map: &IndexMap<String, Value>,
+  metadata: &SerdeMetadata,
+) -> anyhow::Result<()> {
+  if output_path.is_file() {
+    return Err(anyhow::Error::msg("Output path is a file."));
+  }
 
-    let mut translation_path = self.output_path.clone();
-    translation_path.push("translation.json");
+  if !output_path.exists() {
+    fs::create_dir(output_path)?;
+  }
 
-    let mut metadata_path = self.output_path.clone();
-    metadata_path.push("metadata.json");
+  let translation_path = output_path.join("translation.json");
+  let metadata_path = output_path.join("metadata.json");
 
-    let mut translation_file = File::create(&translation_path)?;
-    let mut metadata_file = File::create(&metadata_path)?;
+  let mut translation_file = File::create(&translation_path)?;
+  let mut metadata_file = File::create(&metadata_path)?;
 
-    serde_json::to_writer_pretty(&mut translation_file, &translation_map)?;
-    serde_json::to_writer_pretty(&mut metadata_file, &metadata)?;
+  serde_json::to_writer_pretty(&mut translation_file, translation_map)?;
+  serde_json::to_writer_pretty(&mut metadata_file, metadata)?;
 
-    Ok(())
-  }
+  Ok(())
+}
+
+/// Reads the translations and metadata of a folder written by unpack.
+fn read_unpacked(input_path: &Path) -> anyhow::Result<(IndexMap<String, Value>, SerdeMetadata)> {
+  let translation_path = input_path.join("translation.json");
+  let metadata_path = input_path.join("metadata.json");
+
+  let mut translation_file = File::open(&translation_path)?;
+  let mut metadata_file = File::open(&metadata_path)?;
+
+  let translation_map: IndexMap<String, Value> = serde_json::from_reader(&mut translation_file)?;
+  let metadata: SerdeMetadata = serde_json::from_reader(&mut metadata_file)?;
+
+  Ok((translation_map, metadata))
 }
 
 #[derive(Parser, Debug)]
@@ -171,55 +447,530 @@ pub(crate) struct PackAction {
   lang_dct: PathBuf,
   stringids_dct: PathBuf,
 
-  #[arg(default_value_t = 0.4)]
-  extra_capacity: f32,
+  /// How full the dcts get, lower values trade size for shorter lookups
+  #[clap(short, long, default_value_t = DEFAULT_MAX_LOAD_FACTOR, value_parser = parse_load_factor)]
+  load_factor: f32,
+
+  /// Defaults to the endian the files were unpacked from, or little
+  #[clap(short, long)]
+  endian: Option<Endian>,
+
+  /// Defaults to the encoding the files were unpacked with, see unpack
+  #[clap(long, value_parser = SerdeEncoding::parse)]
+  encoding: Option<SerdeEncoding>,
 }
 
 impl PackAction {
   pub fn execute(&self) -> anyhow::Result<()> {
-    let mut translation_path = self.input_path.clone();
-    translation_path.push("translation.json");
+    let (translation_map, metadata) = read_unpacked(&self.input_path)?;
+
+    pack_translations(
+      &translation_map,
+      metadata,
+      self.load_factor,
+      self.endian.clone(),
+      self.encoding.clone(),
+      &self.lang_dct,
+      &self.stringids_dct,
+    )
+  }
+}
+
+/// Packs the lines into the slots they were unpacked from, or into as many slots as
+/// `load_factor` asks for if the keys changed.
+fn pack_translations(
+  translation_map: &IndexMap<String, Value>,
+  mut metadata: SerdeMetadata,
+  load_factor: f32,
+  endian: Option<Endian>,
+  encoding: Option<SerdeEncoding>,
+  lang_path: &Path,
+  stringids_path: &Path,
+) -> anyhow::Result<()> {
+  let endian = endian.or(metadata.endian.clone()).unwrap_or(Endian::Little);
+  if let Some(encoding) = encoding {
+    metadata.encoding = encoding;
+  }
+
+  let capacity = capacity_for(translation_map.len(), load_factor);
+  let (lang_dct, stringids_dct) = build_dcts(
+    translation_map,
+    metadata,
+    Capacity::PreferUnpacked(capacity),
+    endian,
+  )?;
+
+  write_dct_file(stringids_path, &stringids_dct)?;
+  write_dct_file(lang_path, &lang_dct)?;
+
+  Ok(())
+}
+
+/// Line id, key and text of a line to pack.
+type Line<'a> = (u32, &'a String, &'a str);
+
+enum Capacity {
+  /// The unpacked capacity while the keys are unchanged, or the given one
+  PreferUnpacked(u32),
+  Fixed(u32),
+}
+
+/// Builds the dct of a language and its stringids, returned in that order.
+///
+/// Both get the same capacity, so their lines end up in the same slots. Lines are added in the
+/// order they were unpacked in, followed by new lines in the order of `translation_map`, so the
+/// result only depends on the input.
+fn build_dcts(
+  translation_map: &IndexMap<String, Value>,
+  metadata: SerdeMetadata,
+  capacity: Capacity,
+  endian: Endian,
+) -> anyhow::Result<(DctMap, DctMap)> {
+  let initial_hash_value = metadata.initial_hash_value;
+  let encoding = TextEncoding::try_from(&metadata.encoding)?;
+  let hasher = hasher(&metadata.hasher)?;
+
+  let mut lang_lines = Vec::new();
+  let mut stringids_lines = Vec::new();
+  for (translation_key, translation_text) in translation_map.iter() {
+    // lines whose key is unknown can only be added by their line id
+    let line_id = match parse_hash_key(translation_key) {
+      Some(line_id) => line_id,
+      None => {
+        let line_id = hasher.hash(translation_key.as_bytes(), initial_hash_value);
+        stringids_lines.push((line_id, translation_key, translation_key.as_str()));
+        line_id
+      }
+    };
+
+    match translation_text {
+      Value::Null => Ok(()),
+      Value::String(text) => {
+        lang_lines.push((line_id, translation_key, text.as_str()));
+        Ok(())
+      }
+      _ => Err(anyhow::Error::msg(format!(
+        "{translation_key} has wrong value type {translation_text:?}."
+      ))),
+    }?;
+  }
+
+  let capacity = match (capacity, &metadata.layout) {
+    (Capacity::PreferUnpacked(_), Some(layout))
+      if layout.has_lines(&lang_lines, &stringids_lines) =>
+    {
+      layout.capacity
+    }
+    (Capacity::PreferUnpacked(capacity) | Capacity::Fixed(capacity), _) => capacity,
+  };
+
+  if let Some(layout) = &metadata.layout {
+    sort_by_layout(&mut lang_lines, &layout.lines);
+    sort_by_layout(&mut stringids_lines, &layout.stringids);
+  }
+
+  let footer_entries: Vec<FooterEntry> = metadata
+    .footer_entries
+    .into_iter()
+    .map(|entry| entry.into())
+    .collect();
+
+  let mut lang_dct = DctMap::with_hasher(
+    hasher.clone(),
+    initial_hash_value,
+    capacity,
+    footer_entries.clone(),
+  );
+  lang_dct
+    .validate_footer()
+    .map_err(|err| anyhow!("Invalid footer_entries in the metadata: {err}"))?;
+  let mut stringids_dct = DctMap::with_hasher(hasher, initial_hash_value, capacity, footer_entries);
+  lang_dct.set_endian(endian.clone().into());
+  stringids_dct.set_endian(endian.into());
+  lang_dct.set_max_load_factor(None);
+  stringids_dct.set_max_load_factor(None);
+  lang_dct.set_encoding(encoding.clone());
+  stringids_dct.set_encoding(encoding);
+
+  for (dct, lines) in [
+    (&mut lang_dct, lang_lines),
+    (&mut stringids_dct, stringids_lines),
+  ] {
+    for (line_id, translation_key, text) in lines {
+      dct
+        .add_line_entry_by_hash(line_id, text)
+        .map_err(|err| anyhow!("{translation_key}: {err}"))?;
+    }
+  }
+
+  Ok((lang_dct, stringids_dct))
+}
+
+impl SerdeLayout {
+  fn has_lines(&self, lang_lines: &[Line], stringids_lines: &[Line]) -> bool {
+    let same = |layout: &[u32], lines: &[Line]| {
+      let layout: HashSet<_> = layout.iter().collect();
+      layout.len() == lines.len() && lines.iter().all(|(line_id, _, _)| layout.contains(line_id))
+    };
+
+    let stringids_unknown = self.stringids.is_empty();
+    same(&self.lines, lang_lines) && (stringids_unknown || same(&self.stringids, stringids_lines))
+  }
+}
+
+/// Moves the lines of `layout` to the front, in its order.
+fn sort_by_layout(lines: &mut [Line], layout: &[u32]) {
+  let positions: HashMap<_, _> = layout
+    .iter()
+    .enumerate()
+    .map(|(position, line_id)| (*line_id, position))
+    .collect();
+  lines.sort_by_key(|(line_id, _, _)| positions.get(line_id).copied().unwrap_or(usize::MAX));
+}
+
+/// Where export keeps the metadata of the target language, next to the exported file.
+fn exchange_metadata_path(file: &Path) -> PathBuf {
+  file.with_extension("metadata.json")
+}
+
+fn exchange_format(format: Option<&ExchangeFormat>, file: &Path) -> anyhow::Result<ExchangeFormat> {
+  format
+    .cloned()
+    .or_else(|| ExchangeFormat::from_path(file))
+    .ok_or_else(|| {
+      anyhow!(
+        "Can't tell the format of {}, pass --format",
+        file.to_string_lossy()
+      )
+    })
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct ExportAction {
+  /// The language translators translate from
+  source_dct: PathBuf,
+  /// The language translators translate to
+  target_dct: PathBuf,
+  stringids_dct: PathBuf,
+  /// The metadata of the target language is written next to it, as `<name>.metadata.json`
+  output_file: PathBuf,
+
+  /// Defaults to the extension of the output file
+  #[clap(short, long)]
+  format: Option<ExchangeFormat>,
+
+  #[clap(long, default_value = "en")]
+  source_language: String,
+
+  /// Defaults to the name of the target dct, like `de` for `de.dct`
+  #[clap(long)]
+  target_language: Option<String>,
+
+  #[clap(flatten)]
+  read_options: DctReadOptions,
+}
+
+impl ExportAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    let format = exchange_format(self.format.as_ref(), &self.output_file)?;
 
-    let mut metadata_path = self.input_path.clone();
-    metadata_path.push("metadata.json");
+    let source_dct = read_dct_file(&self.source_dct, &self.read_options)?;
+    let target_dct = read_dct_file(&self.target_dct, &self.read_options)?;
+    let stringids_dct = read_dct_file(&self.stringids_dct, &self.read_options)?;
 
-    let mut translation_file = File::open(&translation_path)?;
-    let mut metadata_file = File::open(&metadata_path)?;
+    let sources = unpack_translations(&source_dct, &stringids_dct);
+    let targets = unpack_translations(&target_dct, &stringids_dct);
 
-    let translation_map: HashMap<String, Value> = serde_json::from_reader(&mut translation_file)?;
-    let metadata: SerdeMetadata = serde_json::from_reader(&mut metadata_file)?;
+    let text = |map: &IndexMap<String, Value>, key: &str| match map.get(key) {
+      Some(Value::String(text)) => Some(text.clone()),
+      _ => None,
+    };
 
-    let initial_hash_value = metadata.initial_hash_value;
-    let footer_entries: Vec<FooterEntry> = metadata
-      .footer_entries
+    let keys: BTreeSet<_> = sources.keys().chain(targets.keys()).collect();
+    let units: Vec<_> = keys
       .into_iter()
-      .map(|entry| entry.into())
+      .map(|key| Unit {
+        key: key.clone(),
+        source: text(&sources, key),
+        target: text(&targets, key),
+      })
       .collect();
 
-    let lang_map_length = translation_map.len();
-    let dct_map_size = (lang_map_length as f32 * (self.extra_capacity + 1.0)).round() as u32;
+    let target_language = match &self.target_language {
+      Some(language) => language.clone(),
+      None => self
+        .target_dct
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string(),
+    };
+    let languages = Languages {
+      source: &self.source_language,
+      target: &target_language,
+    };
+
+    let mut output_file = BufWriter::new(File::create(&self.output_file)?);
+    format.write(&mut output_file, &units, &languages)?;
+
+    let metadata_file = File::create(exchange_metadata_path(&self.output_file))?;
+    serde_json::to_writer_pretty(metadata_file, &SerdeMetadata::from(target_dct))?;
+
+    let translated = units.iter().filter(|unit| unit.target.is_some()).count();
+    println!("Exported {} lines, {translated} translated", units.len());
+
+    Ok(())
+  }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct ImportAction {
+  /// A file written by export, next to its `<name>.metadata.json`
+  input_file: PathBuf,
+  lang_dct: PathBuf,
+  stringids_dct: PathBuf,
 
-    let mut lang_dct = DctMap::new(initial_hash_value, dct_map_size, footer_entries.clone());
-    let mut stringids_dct = DctMap::new(initial_hash_value, dct_map_size, footer_entries);
+  /// How full the dcts get, lower values trade size for shorter lookups
+  #[clap(short, long, default_value_t = DEFAULT_MAX_LOAD_FACTOR, value_parser = parse_load_factor)]
+  load_factor: f32,
 
-    for (translation_key, translation_text) in translation_map.iter() {
-      stringids_dct.add_line_entry(translation_key, translation_key)?;
+  /// Defaults to the extension of the input file
+  #[clap(short, long)]
+  format: Option<ExchangeFormat>,
 
-      match translation_text {
-        Value::Null => Ok(()),
-        Value::String(text) => {
-          lang_dct.add_line_entry(translation_key, text)?;
-          Ok(())
+  /// Defaults to the endian the files were exported from, or little
+  #[clap(short, long)]
+  endian: Option<Endian>,
+
+  /// Defaults to the encoding the files were exported with, see export
+  #[clap(long, value_parser = SerdeEncoding::parse)]
+  encoding: Option<SerdeEncoding>,
+}
+
+impl ImportAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    let format = exchange_format(self.format.as_ref(), &self.input_file)?;
+
+    let input_file = BufReader::new(File::open(&self.input_file)?);
+    let units = format
+      .read(input_file)
+      .map_err(|err| anyhow!("{}: {err}", self.input_file.to_string_lossy()))?;
+
+    let metadata_path = exchange_metadata_path(&self.input_file);
+    let metadata_file = File::open(&metadata_path)
+      .map_err(|err| anyhow!("Can't open {}: {err}", metadata_path.to_string_lossy()))?;
+    let metadata: SerdeMetadata = serde_json::from_reader(BufReader::new(metadata_file))?;
+
+    let mut translation_map = IndexMap::new();
+    for unit in units {
+      let text = unit.target.map_or(Value::Null, Value::String);
+      if translation_map.insert(unit.key.clone(), text).is_some() {
+        return Err(anyhow!("{} appears more than once", unit.key));
+      }
+    }
+
+    let translated = translation_map
+      .values()
+      .filter(|text| text.is_string())
+      .count();
+    println!(
+      "Imported {} lines, {translated} translated",
+      translation_map.len()
+    );
+
+    pack_translations(
+      &translation_map,
+      metadata,
+      self.load_factor,
+      self.endian.clone(),
+      self.encoding.clone(),
+      &self.lang_dct,
+      &self.stringids_dct,
+    )
+  }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct RecoverAction {
+  lang_dct: PathBuf,
+  output_path: PathBuf,
+
+  /// Text files with one candidate key per line
+  #[clap(short, long)]
+  wordlist: Vec<PathBuf>,
+
+  /// Tupperware files or directories, whose keys and strings are tried as keys
+  #[clap(short, long)]
+  scrape: Vec<PathBuf>,
+
+  /// Candidate keys with `{a,b}` alternatives and `{0..99}` ranges, like `MENU_{START,QUIT}`
+  #[clap(short, long)]
+  pattern: Vec<String>,
+
+  #[clap(flatten)]
+  read_options: DctReadOptions,
+}
+
+impl RecoverAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    let lang_dct = read_dct_file(&self.lang_dct, &self.read_options)?;
+    let mut recovery = KeyRecovery::new(&lang_dct);
+
+    for wordlist in &self.wordlist {
+      let words = fs::read(wordlist)?;
+      let words = String::from_utf8_lossy(&words);
+
+      let found = recovery.try_keys(words.lines().map(str::trim));
+      println!("{}: {found} keys", wordlist.to_string_lossy());
+    }
+
+    for path in &self.scrape {
+      let mut files = Vec::new();
+      collect_files(path, &mut files)?;
+
+      let mut found = 0;
+      let mut skipped = 0;
+      for file in files {
+        let mut reader = BufReader::new(File::open(&file)?);
+        if !matryoshka::is_tupperware(&mut reader)? {
+          continue;
         }
-        _ => Err(anyhow::Error::msg(format!(
-          "{translation_key} has wrong value type {translation_text:?}."
-        ))),
-      }?;
+
+        // a dump may have files that are corrupt or use features the decoder lacks
+        let data = match matryoshka::decode(&mut reader) {
+          Ok((data, _)) => data,
+          Err(err) => {
+            println!("Skipping {}: {err}", file.to_string_lossy());
+            skipped += 1;
+            continue;
+          }
+        };
+        let mut strings = Vec::new();
+        scrape_strings(&data, &mut strings);
+        found += recovery.try_keys(strings);
+      }
+      println!(
+        "{}: {found} keys, {skipped} files skipped",
+        path.to_string_lossy()
+      );
+    }
+
+    for pattern in &self.pattern {
+      let found = recovery.try_keys(expand_pattern(pattern)?);
+      println!("{pattern}: {found} keys");
     }
 
-    write_dct_file(&self.stringids_dct, &stringids_dct)?;
-    write_dct_file(&self.lang_dct, &lang_dct)?;
+    let translation_map: IndexMap<_, _> = lang_dct
+      .iter_line_entries()
+      .map(|(line_id, text)| {
+        let key = match recovery.resolved().get(&line_id) {
+          Some(key) => key.clone(),
+          None => hash_key(line_id),
+        };
+        (key, Value::String(text.to_string()))
+      })
+      .collect();
 
-    Ok(())
+    println!(
+      "Recovered {} of {} keys",
+      recovery.resolved().len(),
+      translation_map.len()
+    );
+
+    write_unpacked(
+      &self.output_path,
+      &translation_map,
+      &SerdeMetadata::from(lang_dct),
+    )
+  }
+}
+
+/// Collects every key and string, including the ids of keys like `Texture#id`.
+fn scrape_strings(data: &IndexMap<String, ContainerData>, strings: &mut Vec<String>) {
+  for (key, data) in data {
+    strings.push(key.clone());
+    if let Some((_, id)) = key.split_once('#') {
+      strings.push(id.to_string());
+    }
+
+    for data in data.as_slice() {
+      match data {
+        Data::Container(container) => scrape_strings(container, strings),
+        Data::String(string) => strings.push(string.clone()),
+        Data::StringVec(vec) => strings.extend(vec.iter().cloned()),
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Expands `{a,b}` into both alternatives and `{0..9}` into every number of the range, which is
+/// padded with zeros if the start is, like `{00..99}`.
+fn expand_pattern(pattern: &str) -> anyhow::Result<Vec<String>> {
+  let Some(start) = pattern.find('{') else {
+    return Ok(vec![pattern.to_string()]);
+  };
+  let Some(len) = pattern[start..].find('}') else {
+    return Err(anyhow!("Unclosed `{{` in pattern {pattern}"));
+  };
+
+  let (prefix, group, suffix) = (
+    &pattern[..start],
+    &pattern[start + 1..start + len],
+    &pattern[start + len + 1..],
+  );
+
+  let alternatives: Vec<String> = match group.split_once("..") {
+    Some((from, to)) => {
+      let width = if from.starts_with('0') { from.len() } else { 0 };
+      let from: u32 = from.parse()?;
+      let to: u32 = to.parse()?;
+      (from..=to).map(|n| format!("{n:0width$}")).collect()
+    }
+    None => group.split(',').map(str::to_string).collect(),
+  };
+
+  let suffixes = expand_pattern(suffix)?;
+  Ok(
+    alternatives
+      .iter()
+      .flat_map(|alternative| {
+        suffixes
+          .iter()
+          .map(move |suffix| format!("{prefix}{alternative}{suffix}"))
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::dct::{expand_pattern, hash_key, parse_hash_key, parse_load_factor};
+
+  #[test]
+  fn test_expand_pattern() {
+    assert_eq!(
+      expand_pattern("MENU_{START,QUIT}_{1..2}").unwrap(),
+      ["MENU_START_1", "MENU_START_2", "MENU_QUIT_1", "MENU_QUIT_2"]
+    );
+    assert_eq!(expand_pattern("L{08..10}").unwrap(), ["L08", "L09", "L10"]);
+    assert_eq!(expand_pattern("PLAIN").unwrap(), ["PLAIN"]);
+    assert!(expand_pattern("BROKEN_{A").is_err());
+  }
+
+  #[test]
+  fn test_hash_key() {
+    assert_eq!(hash_key(0x1a2b), "#0x00001a2b");
+    assert_eq!(parse_hash_key(&hash_key(0xdeadbeef)), Some(0xdeadbeef));
+    assert_eq!(parse_hash_key("MENU_START"), None);
+  }
+
+  #[test]
+  fn test_parse_load_factor() {
+    assert_eq!(parse_load_factor("0.7"), Ok(0.7));
+    assert_eq!(parse_load_factor("1"), Ok(1.0));
+    assert!(parse_load_factor("0").is_err());
+    assert!(parse_load_factor("1.5").is_err());
+    assert!(parse_load_factor("NaN").is_err());
   }
 }
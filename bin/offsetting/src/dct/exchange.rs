@@ -0,0 +1,597 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+const XLIFF: &str = "xliff";
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+const FILE: &str = "file";
+const BODY: &str = "body";
+const TRANS_UNIT: &str = "trans-unit";
+const SOURCE: &str = "source";
+const TARGET: &str = "target";
+
+const CSV_HEADER: [&str; 3] = ["key", "source", "target"];
+
+/// A line of the dictionary, by its dct key.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Unit {
+  pub(super) key: String,
+  pub(super) source: Option<String>,
+  /// `None` if the line isn't translated
+  pub(super) target: Option<String>,
+}
+
+pub(super) struct Languages<'a> {
+  pub(super) source: &'a str,
+  pub(super) target: &'a str,
+}
+
+/// File formats of translation tools.
+///
+/// The dct key of every line is written as its context, so lines with the same source text can be
+/// translated differently.
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub(super) enum ExchangeFormat {
+  /// Gettext catalog, with the key as msgctxt
+  Po,
+  /// Gettext template, a catalog without translations
+  Pot,
+  /// XLIFF 1.2, with the key as trans-unit id
+  Xliff,
+  /// Columns key, source and target
+  Csv,
+}
+
+impl Display for ExchangeFormat {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ExchangeFormat::Po => f.write_str("po"),
+      ExchangeFormat::Pot => f.write_str("pot"),
+      ExchangeFormat::Xliff => f.write_str("xliff"),
+      ExchangeFormat::Csv => f.write_str("csv"),
+    }
+  }
+}
+
+impl ExchangeFormat {
+  pub(super) fn from_path(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+      "po" => Some(ExchangeFormat::Po),
+      "pot" => Some(ExchangeFormat::Pot),
+      "xliff" | "xlf" => Some(ExchangeFormat::Xliff),
+      "csv" => Some(ExchangeFormat::Csv),
+      _ => None,
+    }
+  }
+
+  pub(super) fn write<W: Write>(
+    &self,
+    write: W,
+    units: &[Unit],
+    languages: &Languages,
+  ) -> anyhow::Result<()> {
+    match self {
+      ExchangeFormat::Po => write_po(write, units, languages, false),
+      ExchangeFormat::Pot => write_po(write, units, languages, true),
+      ExchangeFormat::Xliff => write_xliff(write, units, languages),
+      ExchangeFormat::Csv => write_csv(write, units),
+    }
+  }
+
+  pub(super) fn read<R: BufRead>(&self, read: R) -> anyhow::Result<Vec<Unit>> {
+    match self {
+      ExchangeFormat::Po | ExchangeFormat::Pot => read_po(read),
+      ExchangeFormat::Xliff => read_xliff(read),
+      ExchangeFormat::Csv => read_csv(read),
+    }
+  }
+}
+
+/// PO and CSV can't tell an empty translation from a missing one, so an empty target only counts
+/// as translated if the source is empty too.
+fn translated(source: &str, target: String) -> Option<String> {
+  (!target.is_empty() || source.is_empty()).then_some(target)
+}
+
+fn write_po<W: Write>(
+  mut write: W,
+  units: &[Unit],
+  languages: &Languages,
+  template: bool,
+) -> anyhow::Result<()> {
+  let language = if template { "" } else { languages.target };
+  let header = format!(
+    "Language: {language}\nMIME-Version: 1.0\nContent-Type: text/plain; charset=UTF-8\n\
+     Content-Transfer-Encoding: 8bit\nX-Source-Language: {}\n",
+    languages.source
+  );
+
+  write_po_string(&mut write, "msgid", "")?;
+  write_po_string(&mut write, "msgstr", &header)?;
+
+  for unit in units {
+    writeln!(write)?;
+    write_po_string(&mut write, "msgctxt", &unit.key)?;
+    write_po_string(
+      &mut write,
+      "msgid",
+      unit.source.as_deref().unwrap_or_default(),
+    )?;
+
+    let target = match &unit.target {
+      Some(target) if !template => target.as_str(),
+      _ => "",
+    };
+    write_po_string(&mut write, "msgstr", target)?;
+  }
+
+  Ok(())
+}
+
+/// Writes text with line breaks as one string per line, like gettext does.
+fn write_po_string<W: Write>(write: &mut W, keyword: &str, text: &str) -> anyhow::Result<()> {
+  if !text.trim_end_matches('\n').contains('\n') {
+    writeln!(write, "{keyword} \"{}\"", escape_po(text))?;
+    return Ok(());
+  }
+
+  writeln!(write, "{keyword} \"\"")?;
+  for line in text.split_inclusive('\n') {
+    writeln!(write, "\"{}\"", escape_po(line))?;
+  }
+
+  Ok(())
+}
+
+fn escape_po(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      '"' => escaped.push_str("\\\""),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+fn unescape_po(text: &str) -> anyhow::Result<String> {
+  let mut unescaped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('\\') => unescaped.push('\\'),
+      Some('"') => unescaped.push('"'),
+      Some('n') => unescaped.push('\n'),
+      Some('r') => unescaped.push('\r'),
+      Some('t') => unescaped.push('\t'),
+      Some(c) => return Err(anyhow!("Unsupported escape sequence \\{c}")),
+      None => return Err(anyhow!("String ends with a lone \\")),
+    }
+  }
+  Ok(unescaped)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PoField {
+  Context,
+  Id,
+  Str,
+}
+
+#[derive(Default)]
+struct PoEntry {
+  context: Option<String>,
+  id: Option<String>,
+  str: Option<String>,
+  fuzzy: bool,
+}
+
+impl PoEntry {
+  fn is_empty(&self) -> bool {
+    self.context.is_none() && self.id.is_none() && self.str.is_none()
+  }
+
+  fn field(&mut self, field: PoField) -> &mut Option<String> {
+    match field {
+      PoField::Context => &mut self.context,
+      PoField::Id => &mut self.id,
+      PoField::Str => &mut self.str,
+    }
+  }
+}
+
+/// Reads a catalog written by [`write_po`]. Entries marked fuzzy count as untranslated, like in
+/// `msgfmt`.
+fn read_po<R: BufRead>(read: R) -> anyhow::Result<Vec<Unit>> {
+  let mut units = Vec::new();
+  let mut entry = PoEntry::default();
+  let mut field = None;
+
+  for (number, line) in read.lines().enumerate() {
+    let line = line?;
+    let line = line.trim();
+    let error = |message: &str| anyhow!("Line {}: {message}", number + 1);
+
+    // a comment or keyword after msgstr starts the next entry
+    let starts_entry = line.is_empty()
+      || line.starts_with('#')
+      || line.starts_with("msgctxt")
+      || line.starts_with("msgid");
+    if starts_entry && entry.str.is_some() {
+      finish_po_entry(std::mem::take(&mut entry), &mut units)?;
+      field = None;
+    }
+
+    if line.is_empty() || line.starts_with("#~") {
+      continue;
+    }
+
+    if let Some(flags) = line.strip_prefix("#,") {
+      entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+      continue;
+    }
+
+    if line.starts_with('#') {
+      continue;
+    }
+
+    let (next_field, string) = if line.starts_with('"') {
+      (
+        field.ok_or_else(|| error("String without a keyword"))?,
+        line,
+      )
+    } else {
+      let (keyword, string) = line
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| error("Expected a keyword and a string"))?;
+      let next_field = match keyword {
+        "msgctxt" => PoField::Context,
+        "msgid" => PoField::Id,
+        "msgstr" => PoField::Str,
+        "msgid_plural" => return Err(error("Plural forms are not supported")),
+        keyword => return Err(error(&format!("Unknown keyword {keyword}"))),
+      };
+      if entry.field(next_field).is_some() {
+        return Err(error(&format!("{keyword} appears twice in one entry")));
+      }
+      (next_field, string.trim_start())
+    };
+
+    let string = string
+      .strip_prefix('"')
+      .and_then(|string| string.strip_suffix('"'))
+      .ok_or_else(|| error("Expected a quoted string"))?;
+    let string = unescape_po(string).map_err(|err| error(&err.to_string()))?;
+
+    entry
+      .field(next_field)
+      .get_or_insert_with(String::new)
+      .push_str(&string);
+    field = Some(next_field);
+  }
+
+  if !entry.is_empty() {
+    finish_po_entry(entry, &mut units)?;
+  }
+
+  Ok(units)
+}
+
+fn finish_po_entry(entry: PoEntry, units: &mut Vec<Unit>) -> anyhow::Result<()> {
+  let source = entry.id.unwrap_or_default();
+
+  let Some(key) = entry.context else {
+    // the header is the only entry without a key
+    if source.is_empty() {
+      return Ok(());
+    }
+    return Err(anyhow!(
+      "msgid \"{source}\" has no msgctxt with its dct key"
+    ));
+  };
+
+  let target = entry
+    .str
+    .filter(|_| !entry.fuzzy)
+    .and_then(|target| translated(&source, target));
+
+  units.push(Unit {
+    key,
+    source: Some(source),
+    target,
+  });
+  Ok(())
+}
+
+fn write_xliff<W: Write>(write: W, units: &[Unit], languages: &Languages) -> anyhow::Result<()> {
+  let mut writer = Writer::new_with_indent(write, b' ', 2);
+
+  writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+  writer.write_event(Event::Start(
+    BytesStart::new(XLIFF).with_attributes([("version", "1.2"), ("xmlns", XLIFF_NAMESPACE)]),
+  ))?;
+  writer.write_event(Event::Start(BytesStart::new(FILE).with_attributes([
+    ("original", "stringids"),
+    ("datatype", "plaintext"),
+    ("source-language", languages.source),
+    ("target-language", languages.target),
+  ])))?;
+  writer.write_event(Event::Start(BytesStart::new(BODY)))?;
+
+  for unit in units {
+    let start = BytesStart::new(TRANS_UNIT).with_attributes([
+      ("id", unit.key.as_str()),
+      ("resname", unit.key.as_str()),
+      ("xml:space", "preserve"),
+    ]);
+    writer.write_event(Event::Start(start))?;
+    write_xliff_text(
+      &mut writer,
+      SOURCE,
+      unit.source.as_deref().unwrap_or_default(),
+    )?;
+    if let Some(target) = &unit.target {
+      write_xliff_text(&mut writer, TARGET, target)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new(TRANS_UNIT)))?;
+  }
+
+  writer.write_event(Event::End(BytesEnd::new(BODY)))?;
+  writer.write_event(Event::End(BytesEnd::new(FILE)))?;
+  writer.write_event(Event::End(BytesEnd::new(XLIFF)))?;
+
+  Ok(())
+}
+
+fn write_xliff_text<W: Write>(
+  writer: &mut Writer<W>,
+  name: &str,
+  text: &str,
+) -> anyhow::Result<()> {
+  // XML parsers normalize a literal `\r` to `\n`, which would lose the CRLF of a line
+  let text = escape(text).replace('\r', "&#13;");
+  writer.write_event(Event::Start(BytesStart::new(name)))?;
+  writer.write_event(Event::Text(BytesText::from_escaped(text)))?;
+  writer.write_event(Event::End(BytesEnd::new(name)))?;
+  Ok(())
+}
+
+/// Reads the units of an XLIFF 1.2 or 2 file. A unit without a target isn't translated.
+fn read_xliff<R: BufRead>(read: R) -> anyhow::Result<Vec<Unit>> {
+  let mut reader = Reader::from_reader(read);
+  let mut buf = Vec::new();
+  let mut units = Vec::new();
+  let mut unit: Option<Unit> = None;
+  let mut found_root = false;
+
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Start(start) => match start.local_name().as_ref() {
+        // XLIFF 2, which CAT tools may convert to, names them unit
+        b"trans-unit" | b"unit" => {
+          let key = start
+            .try_get_attribute("id")?
+            .ok_or_else(|| anyhow!("<{TRANS_UNIT}> without an id"))?
+            .unescape_value()?
+            .to_string();
+          unit = Some(Unit {
+            key,
+            source: None,
+            target: None,
+          });
+        }
+        name @ (b"source" | b"target") => {
+          let is_source = name == SOURCE.as_bytes();
+          let text = read_text(&mut reader)?;
+          let Some(unit) = &mut unit else {
+            return Err(anyhow!(
+              "<{SOURCE}> or <{TARGET}> outside of a <{TRANS_UNIT}>"
+            ));
+          };
+
+          // units of XLIFF 2 may split their text into several segments
+          let field = if is_source {
+            &mut unit.source
+          } else {
+            &mut unit.target
+          };
+          field.get_or_insert_with(String::new).push_str(&text);
+        }
+        b"xliff" => found_root = true,
+        _ => {}
+      },
+      Event::Empty(start) if start.local_name().as_ref() == TARGET.as_bytes() => {
+        if let Some(unit) = &mut unit {
+          unit.target.get_or_insert_with(String::new);
+        }
+      }
+      Event::End(end) if matches!(end.local_name().as_ref(), b"trans-unit" | b"unit") => {
+        units.extend(unit.take());
+      }
+      Event::Eof => break,
+      _ => {}
+    }
+
+    buf.clear();
+  }
+
+  if !found_root {
+    return Err(anyhow!("Missing <{XLIFF}> element"));
+  }
+
+  Ok(units)
+}
+
+fn read_text<R: BufRead>(reader: &mut Reader<R>) -> anyhow::Result<String> {
+  let mut text = String::new();
+  let mut buf = Vec::new();
+
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Text(content) => text.push_str(&content.unescape()?),
+      Event::CData(content) => text.push_str(std::str::from_utf8(&content)?),
+      Event::End(_) => return Ok(text),
+      Event::Comment(_) | Event::PI(_) => {}
+      Event::Eof => return Err(anyhow!("Unexpected end of file")),
+      event => return Err(anyhow!("Expected text, found {event:?}")),
+    }
+
+    buf.clear();
+  }
+}
+
+fn write_csv<W: Write>(write: W, units: &[Unit]) -> anyhow::Result<()> {
+  let mut writer = csv::Writer::from_writer(write);
+
+  writer.write_record(CSV_HEADER)?;
+  for unit in units {
+    writer.write_record([
+      unit.key.as_str(),
+      unit.source.as_deref().unwrap_or_default(),
+      unit.target.as_deref().unwrap_or_default(),
+    ])?;
+  }
+  writer.flush()?;
+
+  Ok(())
+}
+
+fn read_csv<R: BufRead>(read: R) -> anyhow::Result<Vec<Unit>> {
+  let mut reader = csv::Reader::from_reader(read);
+
+  let headers = reader.headers()?.clone();
+  let column = |name: &str| {
+    headers
+      .iter()
+      .position(|header| header.trim().eq_ignore_ascii_case(name))
+      .ok_or_else(|| anyhow!("Missing column {name}"))
+  };
+  let [key, source, target] = [column("key")?, column("source")?, column("target")?];
+
+  let mut units = Vec::new();
+  for record in reader.records() {
+    let record = record?;
+    let field = |column: usize| record.get(column).unwrap_or_default().to_string();
+
+    let source = field(source);
+    units.push(Unit {
+      key: field(key),
+      target: translated(&source, field(target)),
+      source: Some(source),
+    });
+  }
+
+  Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::dct::exchange::{ExchangeFormat, Languages, Unit};
+
+  fn units() -> Vec<Unit> {
+    let unit = |key: &str, source: &str, target: Option<&str>| Unit {
+      key: key.to_string(),
+      source: Some(source.to_string()),
+      target: target.map(str::to_string),
+    };
+
+    vec![
+      unit("MENU_START", "Start", Some("Starten")),
+      unit("MENU_QUIT", "Quit", None),
+      unit("QUOTE", "Say \"hi\"\\", Some("Sag \"hallo\"\\")),
+      unit("LINES", "One\nTwo\n", Some("Eins\r\n\tZwei\n")),
+      unit("CRLF", "One\r\nTwo\r", Some("Eins\r\nZwei\r")),
+      unit("EMPTY", "", Some("")),
+      unit("MARKUP", "<b>A & B</b>", Some("  <b>A & B</b>  ")),
+      unit("#0x00001a2b", "Only, by id", Some("Nur, per id")),
+    ]
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let languages = Languages {
+      source: "en",
+      target: "de",
+    };
+
+    for format in [
+      ExchangeFormat::Po,
+      ExchangeFormat::Xliff,
+      ExchangeFormat::Csv,
+    ] {
+      let mut buf = Vec::new();
+      format.write(&mut buf, &units(), &languages).unwrap();
+      if let ExchangeFormat::Xliff = format {
+        assert!(!buf.contains(&b'\r'), "{format} has a literal \\r");
+      }
+      let read = format.read(buf.as_slice()).unwrap();
+
+      assert_eq!(read, units(), "{format}");
+    }
+  }
+
+  #[test]
+  fn test_template() {
+    let languages = Languages {
+      source: "en",
+      target: "de",
+    };
+
+    let mut buf = Vec::new();
+    ExchangeFormat::Pot
+      .write(&mut buf, &units(), &languages)
+      .unwrap();
+    let read = ExchangeFormat::Pot.read(buf.as_slice()).unwrap();
+
+    assert_eq!(read.len(), units().len());
+    assert!(read
+      .iter()
+      .all(|unit| unit.target.is_none() || unit.key == "EMPTY"));
+  }
+
+  #[test]
+  fn test_read_po() {
+    let po = r#"
+# translator comment
+msgid ""
+msgstr "Language: de\n"
+
+#, fuzzy
+msgctxt "FUZZY"
+msgid "Guess"
+msgstr "Raten"
+
+msgctxt "SPLIT"
+msgid ""
+"Two "
+"parts"
+msgstr "Zwei Teile"
+"#;
+
+    let units = ExchangeFormat::Po.read(po.as_bytes()).unwrap();
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0].target, None);
+    assert_eq!(units[1].source.as_deref(), Some("Two parts"));
+    assert_eq!(units[1].target.as_deref(), Some("Zwei Teile"));
+
+    assert!(ExchangeFormat::Po
+      .read("msgid \"No key\"\nmsgstr \"\"\n".as_bytes())
+      .is_err());
+  }
+}
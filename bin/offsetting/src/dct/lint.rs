@@ -0,0 +1,381 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use clap::Parser;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::dct::read_unpacked;
+
+/// What a check found, errors fail the lint and warnings only with `--strict`.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Severity {
+  Error,
+  Warning,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Check {
+  /// Format placeholders or markup tags differ from the source
+  Placeholders,
+  /// Longer than the length budget of the key, not counting markup tags
+  Length,
+  /// Empty while the source isn't
+  Empty,
+  /// Ends in whitespace while the source doesn't
+  TrailingWhitespace,
+  /// Characters missing from the `--charset` file
+  Unrenderable,
+}
+
+impl Check {
+  fn severity(&self) -> Severity {
+    match self {
+      Check::Placeholders | Check::Length => Severity::Error,
+      Check::Empty | Check::TrailingWhitespace | Check::Unrenderable => Severity::Warning,
+    }
+  }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Issue {
+  key: String,
+  check: Check,
+  severity: Severity,
+  message: String,
+}
+
+impl Issue {
+  fn new(key: &str, check: Check, message: String) -> Self {
+    Self {
+      key: key.to_string(),
+      check,
+      severity: check.severity(),
+      message,
+    }
+  }
+}
+
+#[derive(Serialize, Debug)]
+struct LintReport {
+  errors: usize,
+  warnings: usize,
+  issues: Vec<Issue>,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct LintAction {
+  /// Folder written by unpack, of the language translators translate from
+  source_path: PathBuf,
+  /// Folder written by unpack, of the translation to check
+  target_path: PathBuf,
+
+  /// Lines longer than this many characters are errors
+  #[clap(long)]
+  max_length: Option<usize>,
+
+  /// JSON file with the most characters of every key, like `{"MENU_START": 24}`, keys that aren't
+  /// listed get `--max-length`
+  #[clap(long)]
+  length_budget: Option<PathBuf>,
+
+  /// UTF-8 text file with every character the game font can render, line breaks are always allowed
+  #[clap(long)]
+  charset: Option<PathBuf>,
+
+  /// Writes the JSON report to this file instead of stdout
+  #[clap(short, long)]
+  output: Option<PathBuf>,
+
+  /// Warnings fail the lint too
+  #[clap(long)]
+  strict: bool,
+}
+
+impl LintAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    let (sources, _) = read_unpacked(&self.source_path)?;
+    let (targets, _) = read_unpacked(&self.target_path)?;
+
+    let budgets: HashMap<String, usize> = match &self.length_budget {
+      Some(path) => serde_json::from_reader(BufReader::new(File::open(path)?))
+        .map_err(|err| anyhow!("{}: {err}", path.to_string_lossy()))?,
+      None => HashMap::new(),
+    };
+    let charset = match &self.charset {
+      Some(path) => Some(read_charset(path)?),
+      None => None,
+    };
+
+    let mut issues = Vec::new();
+    for (key, target) in &targets {
+      let Value::String(target) = target else {
+        continue;
+      };
+      let source = match sources.get(key) {
+        Some(Value::String(source)) => Some(source.as_str()),
+        _ => None,
+      };
+      let budget = budgets.get(key).copied().or(self.max_length);
+
+      issues.extend(lint_line(key, source, target, budget, charset.as_ref()));
+    }
+
+    let errors = issues
+      .iter()
+      .filter(|issue| issue.severity == Severity::Error)
+      .count();
+    let report = LintReport {
+      errors,
+      warnings: issues.len() - errors,
+      issues,
+    };
+
+    match &self.output {
+      Some(output) => serde_json::to_writer_pretty(BufWriter::new(File::create(output)?), &report)?,
+      None => {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &report)?;
+        writeln!(stdout)?;
+      }
+    }
+
+    if report.errors > 0 || (self.strict && report.warnings > 0) {
+      return Err(anyhow!(
+        "Lint failed with {} errors and {} warnings",
+        report.errors,
+        report.warnings
+      ));
+    }
+
+    Ok(())
+  }
+}
+
+/// The characters of a charset file, with line breaks.
+fn read_charset(path: &Path) -> anyhow::Result<HashSet<char>> {
+  let text =
+    fs::read_to_string(path).map_err(|err| anyhow!("{}: {err}", path.to_string_lossy()))?;
+  Ok(text.chars().chain(['\n', '\r']).collect())
+}
+
+fn lint_line(
+  key: &str,
+  source: Option<&str>,
+  target: &str,
+  budget: Option<usize>,
+  charset: Option<&HashSet<char>>,
+) -> Vec<Issue> {
+  let mut issues = Vec::new();
+
+  if let Some(source) = source {
+    let (missing, unexpected) = token_difference(&tokens(source), &tokens(target));
+    if !missing.is_empty() || !unexpected.is_empty() {
+      let mut message = Vec::new();
+      if !missing.is_empty() {
+        message.push(format!("missing {}", missing.join(" ")));
+      }
+      if !unexpected.is_empty() {
+        message.push(format!("unexpected {}", unexpected.join(" ")));
+      }
+      issues.push(Issue::new(key, Check::Placeholders, message.join(", ")));
+    }
+
+    if target.is_empty() && !source.is_empty() {
+      issues.push(Issue::new(key, Check::Empty, "empty".to_string()));
+    }
+
+    let trailing = |text: &str| text.ends_with(char::is_whitespace);
+    if trailing(target) && !trailing(source) {
+      issues.push(Issue::new(
+        key,
+        Check::TrailingWhitespace,
+        "ends in whitespace".to_string(),
+      ));
+    }
+  }
+
+  // markup tags aren't rendered, while placeholders are replaced by about as much text
+  let markup: usize = tokens(target)
+    .iter()
+    .filter(|token| token.starts_with('<'))
+    .map(|token| token.chars().count())
+    .sum();
+  let length = target.chars().count() - markup;
+  if let Some(budget) = budget.filter(|budget| length > *budget) {
+    issues.push(Issue::new(
+      key,
+      Check::Length,
+      format!("{length} characters, the budget is {budget}"),
+    ));
+  }
+
+  if let Some(charset) = charset {
+    let mut unrenderable: Vec<_> = target.chars().filter(|c| !charset.contains(c)).collect();
+    unrenderable.sort_unstable();
+    unrenderable.dedup();
+    if !unrenderable.is_empty() {
+      let chars: Vec<_> = unrenderable
+        .iter()
+        .map(|c| format!("{c:?} (U+{:04X})", *c as u32))
+        .collect();
+      issues.push(Issue::new(
+        key,
+        Check::Unrenderable,
+        format!("not in the charset: {}", chars.join(", ")),
+      ));
+    }
+  }
+
+  issues
+}
+
+/// Tokens of `source` that `target` lacks, and tokens of `target` that `source` lacks, counting
+/// repeated tokens. Translations may reorder them.
+fn token_difference<'a>(source: &[&'a str], target: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+  let mut unexpected = target.to_vec();
+  let mut missing = Vec::new();
+  for token in source {
+    match unexpected.iter().position(|other| other == token) {
+      Some(position) => {
+        unexpected.remove(position);
+      }
+      None => missing.push(*token),
+    }
+  }
+
+  (missing, unexpected)
+}
+
+/// Format placeholders like `%s`, `%1$d` and `{0}`, and markup tags like `<b>` and `</b>`.
+fn tokens(text: &str) -> Vec<&str> {
+  let bytes = text.as_bytes();
+  let mut tokens = Vec::new();
+
+  let mut start = 0;
+  while start < bytes.len() {
+    // `%%` is a literal percent sign
+    if bytes[start..].starts_with(b"%%") {
+      start += 2;
+      continue;
+    }
+
+    let end = match bytes[start] {
+      b'%' => printf_end(bytes, start + 1),
+      b'{' => text[start..]
+        .find('}')
+        .map(|end| start + end + 1)
+        .filter(|end| {
+          let name = &text[start + 1..end - 1];
+          !name.is_empty()
+            && name
+              .chars()
+              .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+        }),
+      b'<' => text[start..]
+        .find('>')
+        .map(|end| start + end + 1)
+        .filter(|end| {
+          let tag = text[start + 1..end - 1].trim_start_matches('/');
+          tag.starts_with(|c: char| c.is_ascii_alphabetic()) && !tag.contains('<')
+        }),
+      _ => None,
+    };
+
+    match end {
+      Some(end) => {
+        tokens.push(&text[start..end]);
+        start = end;
+      }
+      None => start += 1,
+    }
+  }
+
+  tokens
+}
+
+/// The end of a printf conversion like `%-5.2f` that starts before `start`, without the space
+/// flag, as `100% sure` isn't one.
+fn printf_end(bytes: &[u8], start: usize) -> Option<usize> {
+  let skip = |mut end: usize, allowed: &[u8]| {
+    while end < bytes.len() && allowed.contains(&bytes[end]) {
+      end += 1;
+    }
+    end
+  };
+  const DIGITS: &[u8] = b"0123456789";
+
+  // positional argument, like `%1$s`
+  let mut end = skip(start, DIGITS);
+  end = if end > start && bytes.get(end) == Some(&b'$') {
+    end + 1
+  } else {
+    start
+  };
+
+  end = skip(end, b"-+0#");
+  end = skip(end, b"0123456789*");
+  if bytes.get(end) == Some(&b'.') {
+    end = skip(end + 1, b"0123456789*");
+  }
+  end = skip(end, b"hlLqjzt");
+
+  bytes
+    .get(end)
+    .filter(|conversion| b"diouxXeEfFgGaAcsp".contains(conversion))
+    .map(|_| end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use crate::dct::lint::{lint_line, tokens, Check};
+
+  #[test]
+  fn test_tokens() {
+    assert_eq!(
+      tokens("%s has %1$d of {0} <b>items</b> at 100%% or 100% sure, {not a name} a < b >"),
+      vec!["%s", "%1$d", "{0}", "<b>", "</b>"]
+    );
+    assert_eq!(tokens("%-5.2f%%%lu"), vec!["%-5.2f", "%lu"]);
+  }
+
+  #[test]
+  fn test_lint_line() {
+    let checks = |source, target, budget, charset| {
+      lint_line("KEY", source, target, budget, charset)
+        .into_iter()
+        .map(|issue| issue.check)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(checks(Some("%s and %d"), "%d und %s", None, None), vec![]);
+    assert_eq!(
+      checks(Some("<b>%s</b>"), "<b>%d ", Some(2), None),
+      vec![
+        Check::Placeholders,
+        Check::TrailingWhitespace,
+        Check::Length
+      ]
+    );
+    assert_eq!(
+      checks(Some("<b>%s</b>"), "<b>%s</b>", Some(2), None),
+      vec![]
+    );
+    assert_eq!(checks(Some("Hello"), "", None, None), vec![Check::Empty]);
+    assert_eq!(checks(None, "", None, None), vec![]);
+
+    let charset: HashSet<_> = "Grüne\n".chars().collect();
+    assert_eq!(
+      checks(None, "Grüße", None, Some(&charset)),
+      vec![Check::Unrenderable]
+    );
+    assert_eq!(checks(None, "Grün\nGrüne", None, Some(&charset)), vec![]);
+  }
+}
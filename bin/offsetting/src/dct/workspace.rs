@@ -0,0 +1,322 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use clap::Parser;
+use indctive::dct_map::{capacity_for, DEFAULT_MAX_LOAD_FACTOR};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dct::{
+  build_dcts, parse_hash_key, parse_load_factor, read_dct_file, unpack_translations,
+  write_dct_file, Capacity, DctReadOptions, SerdeEncoding, SerdeFooterEntry, SerdeMetadata,
+};
+use crate::oct::Endian;
+
+const STRINGIDS: &str = "stringids";
+const DCT_EXTENSION: &str = "dct";
+const KEY_COLUMN: &str = "key";
+
+const TABLE_FILE: &str = "table.csv";
+const METADATA_FILE: &str = "metadata.json";
+const REPORT_FILE: &str = "report.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WorkspaceMetadata {
+  /// Slots of every dct, packing keeps it so lines stay in the same slot in every language
+  capacity: u32,
+  stringids: SerdeMetadata,
+  languages: IndexMap<String, LanguageMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LanguageMetadata {
+  #[serde(flatten)]
+  metadata: SerdeMetadata,
+  /// Lines translated to an empty string, which the table can't tell from missing ones
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  empty_lines: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct LanguageReport {
+  /// Keys of stringids without a line in this language
+  missing: Vec<String>,
+  /// Lines that are empty or the same as in the source language
+  untranslated: Vec<String>,
+}
+
+fn dct_path(dct_dir: &Path, name: &str) -> PathBuf {
+  dct_dir.join(format!("{name}.{DCT_EXTENSION}"))
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct UnpackAllAction {
+  /// Folder with `stringids.dct` and a `<lang>.dct` per language
+  dct_dir: PathBuf,
+  output_path: PathBuf,
+
+  /// Lines that are the same as in this language count as untranslated
+  #[clap(long, default_value = "en")]
+  source_language: String,
+
+  #[clap(flatten)]
+  read_options: DctReadOptions,
+}
+
+impl UnpackAllAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    if self.output_path.is_file() {
+      return Err(anyhow::Error::msg("Output path is a file."));
+    }
+
+    let stringids_dct = read_dct_file(dct_path(&self.dct_dir, STRINGIDS), &self.read_options)?;
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&self.dct_dir)? {
+      let path = entry?.path();
+      let is_dct = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(DCT_EXTENSION));
+      if is_dct && path.file_stem().is_some_and(|stem| stem != STRINGIDS) {
+        paths.push(path);
+      }
+    }
+    paths.sort();
+
+    if paths.is_empty() {
+      return Err(anyhow!(
+        "No <lang>.{DCT_EXTENSION} next to {STRINGIDS}.{DCT_EXTENSION} in {}",
+        self.dct_dir.to_string_lossy()
+      ));
+    }
+
+    let mut languages = IndexMap::new();
+    for path in paths {
+      let language = path.file_stem().unwrap().to_string_lossy().to_string();
+      let lang_dct = read_dct_file(&path, &self.read_options)?;
+      languages.insert(language, lang_dct);
+    }
+
+    let columns: IndexMap<_, _> = languages
+      .iter()
+      .map(|(language, lang_dct)| {
+        (
+          language.clone(),
+          unpack_translations(lang_dct, &stringids_dct),
+        )
+      })
+      .collect();
+
+    // stringids keys in slot order, followed by the lines of every language without a key
+    let keys: IndexSet<_> = columns.values().flat_map(IndexMap::keys).collect();
+
+    let mut reports = IndexMap::new();
+    for (language, column) in &columns {
+      let source = columns
+        .get(&self.source_language)
+        .filter(|_| *language != self.source_language);
+
+      let mut report = LanguageReport::default();
+      for key in &keys {
+        match column.get(*key) {
+          Some(Value::String(text)) => {
+            let is_source = source.and_then(|source| source.get(*key)) == column.get(*key);
+            if text.is_empty() || is_source {
+              report.untranslated.push(key.to_string());
+            }
+          }
+          _ if parse_hash_key(key).is_none() => report.missing.push(key.to_string()),
+          _ => {}
+        }
+      }
+
+      println!(
+        "{language}: {} missing, {} untranslated",
+        report.missing.len(),
+        report.untranslated.len()
+      );
+      reports.insert(language.clone(), report);
+    }
+
+    if !self.output_path.exists() {
+      fs::create_dir(&self.output_path)?;
+    }
+
+    let mut table = csv::Writer::from_path(self.output_path.join(TABLE_FILE))?;
+    table.write_record(
+      [KEY_COLUMN]
+        .into_iter()
+        .chain(columns.keys().map(String::as_str)),
+    )?;
+    for key in &keys {
+      let cells = columns.values().map(|column| match column.get(*key) {
+        Some(Value::String(text)) => text.as_str(),
+        _ => "",
+      });
+      table.write_record([key.as_str()].into_iter().chain(cells))?;
+    }
+    table.flush()?;
+
+    let metadata = WorkspaceMetadata {
+      capacity: stringids_dct.get_max_capacity(),
+      stringids: SerdeMetadata::from(stringids_dct),
+      languages: languages
+        .into_iter()
+        .map(|(language, lang_dct)| {
+          let empty_lines = columns[&language]
+            .iter()
+            .filter(|(_, text)| text.as_str() == Some(""))
+            .map(|(key, _)| key.clone())
+            .collect();
+          let metadata = LanguageMetadata {
+            metadata: SerdeMetadata::from(lang_dct),
+            empty_lines,
+          };
+          (language, metadata)
+        })
+        .collect(),
+    };
+
+    let metadata_file = BufWriter::new(File::create(self.output_path.join(METADATA_FILE))?);
+    serde_json::to_writer_pretty(metadata_file, &metadata)?;
+
+    let report_file = BufWriter::new(File::create(self.output_path.join(REPORT_FILE))?);
+    serde_json::to_writer_pretty(report_file, &reports)?;
+
+    Ok(())
+  }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct PackAllAction {
+  input_path: PathBuf,
+  /// Folder to write `stringids.dct` and a `<lang>.dct` per language column to
+  dct_dir: PathBuf,
+
+  /// How full the dcts get, if the table has more lines than fit into the unpacked capacity
+  #[clap(short, long, default_value_t = DEFAULT_MAX_LOAD_FACTOR, value_parser = parse_load_factor)]
+  load_factor: f32,
+
+  /// Defaults to the endian the files were unpacked from, or little
+  #[clap(short, long)]
+  endian: Option<Endian>,
+
+  /// Stores the strings of every file in this encoding instead of the one it was unpacked with
+  #[clap(long, value_parser = SerdeEncoding::parse)]
+  encoding: Option<SerdeEncoding>,
+}
+
+impl PackAllAction {
+  pub fn execute(&self) -> anyhow::Result<()> {
+    let metadata_file = BufReader::new(File::open(self.input_path.join(METADATA_FILE))?);
+    let mut metadata: WorkspaceMetadata = serde_json::from_reader(metadata_file)?;
+
+    let mut table = csv::Reader::from_path(self.input_path.join(TABLE_FILE))?;
+    let headers = table.headers()?.clone();
+    if headers.get(0) != Some(KEY_COLUMN) {
+      return Err(anyhow!(
+        "The first column of {TABLE_FILE} must be {KEY_COLUMN}"
+      ));
+    }
+
+    let mut rows = IndexMap::new();
+    for (row, record) in table.records().enumerate() {
+      let record = record?;
+      let key = record.get(0).unwrap_or_default().to_string();
+      if key.is_empty() {
+        return Err(anyhow!("Row {} has no key", row + 2));
+      }
+      if rows.insert(key.clone(), record).is_some() {
+        return Err(anyhow!("{key} appears more than once"));
+      }
+    }
+
+    let capacity = if rows.len() <= metadata.capacity as usize {
+      metadata.capacity
+    } else {
+      let capacity = capacity_for(rows.len(), self.load_factor);
+      println!(
+        "Growing capacity from {} to {capacity} for {} lines",
+        metadata.capacity,
+        rows.len()
+      );
+      capacity
+    };
+
+    let endian = self
+      .endian
+      .clone()
+      .or(metadata.stringids.endian.clone())
+      .unwrap_or(Endian::Little);
+
+    if !self.dct_dir.exists() {
+      fs::create_dir_all(&self.dct_dir)?;
+    }
+
+    let mut stringids_dct = None;
+    for (column, language) in headers.iter().enumerate().skip(1) {
+      let Some(language_metadata) = metadata.languages.shift_remove(language) else {
+        return Err(anyhow!(
+          "Language {language} has no metadata, copy the entry of another language in {METADATA_FILE}"
+        ));
+      };
+
+      let translation_map: IndexMap<_, _> = rows
+        .iter()
+        .map(|(key, record)| {
+          let text = match record.get(column).unwrap_or_default() {
+            "" if !language_metadata.empty_lines.contains(key) => Value::Null,
+            text => Value::String(text.to_string()),
+          };
+          (key.clone(), text)
+        })
+        .collect();
+
+      // every language is hashed like stringids, so its keys find their lines
+      let mut lang_metadata = SerdeMetadata {
+        hasher: metadata.stringids.hasher.clone(),
+        initial_hash_value: metadata.stringids.initial_hash_value,
+        ..language_metadata.metadata
+      };
+      if let Some(encoding) = &self.encoding {
+        lang_metadata.encoding = encoding.clone();
+      }
+      if let (Some(layout), Some(stringids_layout)) =
+        (&mut lang_metadata.layout, &metadata.stringids.layout)
+      {
+        layout.stringids = stringids_layout.lines.clone();
+      }
+
+      let (lang_dct, stringids) = build_dcts(
+        &translation_map,
+        lang_metadata,
+        Capacity::Fixed(capacity),
+        endian.clone(),
+      )?;
+      write_dct_file(dct_path(&self.dct_dir, language), &lang_dct)?;
+      stringids_dct = Some(stringids);
+    }
+
+    let Some(mut stringids_dct) = stringids_dct else {
+      return Err(anyhow!("{TABLE_FILE} has no language columns"));
+    };
+    stringids_dct.footer_entries = metadata
+      .stringids
+      .footer_entries
+      .into_iter()
+      .map(SerdeFooterEntry::into)
+      .collect();
+    write_dct_file(dct_path(&self.dct_dir, STRINGIDS), &stringids_dct)?;
+
+    println!(
+      "Packed {} languages with {} lines",
+      headers.len() - 1,
+      rows.len()
+    );
+
+    Ok(())
+  }
+}
@@ -1,12 +1,27 @@
 use std::fmt::{Display, Formatter};
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 
+use anyhow::anyhow;
 use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
 use indexmap::IndexMap;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use matryoshka::{ContainerData, Data};
+use matryoshka::{Change, ContainerData, Data, Schema};
+
+use crate::oct::assets::{
+  assets_path, find_and_embed_files, find_and_extract_files, ExtractionRule, ASSETS_EXTENSION,
+};
+
+mod assets;
+mod schema;
+mod texture;
+mod toml;
+mod xml;
 
 /// Encodes/decodes Tupperware files (.oct/.mer/.bent/etc.)
 #[derive(Parser)]
@@ -15,16 +30,21 @@ pub(super) struct OctModule {
   command: Command,
 }
 
-#[derive(Clone, ValueEnum)]
+#[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Endian {
   Big,
   Little,
 }
 
-#[derive(Clone, ValueEnum)]
+#[derive(Clone, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Format {
   Json,
   Yaml,
+  Toml,
+  Ron,
+  Xml,
 }
 
 impl Display for Endian {
@@ -41,10 +61,57 @@ impl Display for Format {
     match self {
       Format::Json => f.write_str("json"),
       Format::Yaml => f.write_str("yaml"),
+      Format::Toml => f.write_str("toml"),
+      Format::Ron => f.write_str("ron"),
+      Format::Xml => f.write_str("xml"),
     }
   }
 }
 
+impl Format {
+  fn from_path(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+      "json" => Some(Format::Json),
+      "yaml" | "yml" => Some(Format::Yaml),
+      "toml" => Some(Format::Toml),
+      "ron" => Some(Format::Ron),
+      "xml" => Some(Format::Xml),
+      _ => None,
+    }
+  }
+
+  fn write<W: Write>(
+    &self,
+    write: &mut W,
+    data: &IndexMap<String, ContainerData>,
+  ) -> anyhow::Result<()> {
+    match self {
+      Format::Json => serde_json::to_writer_pretty(write, data)?,
+      Format::Yaml => serde_yaml::to_writer(write, data)?,
+      Format::Toml => write.write_all(toml::to_document(data)?.to_string().as_bytes())?,
+      Format::Ron => ron::ser::to_writer_pretty(write, data, ron::ser::PrettyConfig::default())?,
+      Format::Xml => xml::to_writer(write, data)?,
+    }
+
+    Ok(())
+  }
+
+  fn read<R: BufRead>(&self, mut read: R) -> anyhow::Result<IndexMap<String, ContainerData>> {
+    Ok(match self {
+      Format::Json => serde_json::from_reader(read)?,
+      Format::Yaml => serde_yaml::from_reader(read)?,
+      Format::Toml => {
+        let mut string = String::new();
+        read.read_to_string(&mut string)?;
+        toml_edit::de::from_str(&string)?
+      }
+      Format::Ron => ron::de::from_reader(read)?,
+      Format::Xml => xml::from_reader(read)?,
+    })
+  }
+}
+
 impl From<Endian> for matryoshka::Endian {
   fn from(value: Endian) -> Self {
     match value {
@@ -54,25 +121,140 @@ impl From<Endian> for matryoshka::Endian {
   }
 }
 
+impl From<matryoshka::Endian> for Endian {
+  fn from(value: matryoshka::Endian) -> Self {
+    match value {
+      matryoshka::Endian::Big => Self::Big,
+      matryoshka::Endian::Little => Self::Little,
+    }
+  }
+}
+
+/// Written next to a decoded file, so encoding it again reproduces the original layout.
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+  endian: Endian,
+  format: Format,
+  #[serde(default)]
+  extraction_rules: Vec<ExtractionRule>,
+}
+
+const METADATA_EXTENSION: &str = "meta";
+
+impl Metadata {
+  fn path(file: &Path) -> PathBuf {
+    file.with_extension(METADATA_EXTENSION)
+  }
+
+  fn read(file: &Path) -> anyhow::Result<Option<Self>> {
+    let path = Self::path(file);
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_reader(BufReader::new(File::open(
+      path,
+    )?))?))
+  }
+
+  fn write(&self, file: &Path) -> anyhow::Result<()> {
+    let file = BufWriter::new(File::create(Self::path(file))?);
+    serde_json::to_writer_pretty(file, self)?;
+    Ok(())
+  }
+}
+
 #[derive(Subcommand)]
 enum Command {
   Decode {
     in_file: PathBuf,
     out_file: PathBuf,
-    #[clap(short, long, default_value_t = Format::Json)]
-    format: Format,
+    /// Defaults to the extension of the output file, or json
+    #[clap(short, long)]
+    format: Option<Format>,
+    /// Extracts embedded textures, same as `-x Texture#*:SourceFilePath:Data:dds`
     #[clap(short = 't', long)]
     unpack_textures: bool,
+    /// Extracts embedded files by KEY:PATH_FIELD:DATA_FIELD:EXTENSION, KEY may contain `*`
+    #[clap(short = 'x', long = "extract")]
+    extraction_rules: Vec<ExtractionRule>,
+    /// Converts extracted dds textures to png, encode compresses them back to the original format
+    #[clap(short = 'p', long)]
+    png: bool,
   },
   Encode {
     in_file: PathBuf,
     out_file: PathBuf,
-    #[clap(short, long, default_value_t = Endian::Little)]
-    endian: Endian,
+    /// Defaults to the endian the input was decoded from, or little
+    #[clap(short, long)]
+    endian: Option<Endian>,
+    /// Defaults to the extension of the input file, the format it was decoded to, or json
+    #[clap(short, long)]
+    format: Option<Format>,
+    /// Embeds textures, for files decoded without metadata
+    #[clap(short = 't', long)]
+    repack_textures: bool,
+    /// Embeds files by KEY:PATH_FIELD:DATA_FIELD:EXTENSION, in addition to the decode rules
+    #[clap(short = 'x', long = "embed")]
+    extraction_rules: Vec<ExtractionRule>,
+    /// Refuses to write the file if it doesn't match a schema written by `oct schema`
+    #[clap(short, long)]
+    schema: Option<PathBuf>,
+  },
+  /// Decodes every Tupperware file below a directory, mirroring the directory tree
+  DecodeDir {
+    in_dir: PathBuf,
+    out_dir: PathBuf,
     #[clap(short, long, default_value_t = Format::Json)]
     format: Format,
+    /// Extracts embedded textures, same as `-x Texture#*:SourceFilePath:Data:dds`
+    #[clap(short = 't', long)]
+    unpack_textures: bool,
+    /// Extracts embedded files by KEY:PATH_FIELD:DATA_FIELD:EXTENSION, KEY may contain `*`
+    #[clap(short = 'x', long = "extract")]
+    extraction_rules: Vec<ExtractionRule>,
+    /// Converts extracted dds textures to png, encode compresses them back to the original format
+    #[clap(short = 'p', long)]
+    png: bool,
+  },
+  /// Encodes every file written by `oct decode-dir` below a directory, mirroring the directory tree
+  EncodeDir {
+    in_dir: PathBuf,
+    out_dir: PathBuf,
+    /// Defaults to the endian each file was decoded from, or little
+    #[clap(short, long)]
+    endian: Option<Endian>,
+    /// Embeds textures, for files decoded without metadata
     #[clap(short = 't', long)]
     repack_textures: bool,
+    /// Embeds files by KEY:PATH_FIELD:DATA_FIELD:EXTENSION, in addition to the decode rules
+    #[clap(short = 'x', long = "embed")]
+    extraction_rules: Vec<ExtractionRule>,
+    /// Skips files that don't match a schema written by `oct schema`
+    #[clap(short, long)]
+    schema: Option<PathBuf>,
+  },
+  /// Infers a schema from example files, directories are searched recursively
+  Schema {
+    #[clap(required = true)]
+    in_files: Vec<PathBuf>,
+    #[clap(short, long)]
+    out_file: PathBuf,
+    /// Writes a JSON Schema of the decoded json, instead of a schema for `oct encode --schema`
+    #[clap(long)]
+    json_schema: bool,
+  },
+  /// Lists the nodes that differ between two files
+  Diff {
+    old_file: PathBuf,
+    new_file: PathBuf,
+  },
+  /// Combines the changes of two files edited from a common base
+  Merge {
+    base_file: PathBuf,
+    ours_file: PathBuf,
+    theirs_file: PathBuf,
+    out_file: PathBuf,
   },
 }
 
@@ -84,28 +266,11 @@ impl OctModule {
         out_file,
         format,
         unpack_textures,
+        extraction_rules,
+        png,
       } => {
-        let mut file = BufReader::new(File::open(in_file)?);
-
-        let (mut data, endian) = matryoshka::decode(&mut file)?;
-        println!("Read file with endian: {}", endian);
-
-        if unpack_textures {
-          let texture_output = out_file.with_extension("textures");
-
-          println!(
-            "Extracting textures to: {}",
-            texture_output.to_string_lossy()
-          );
-          find_and_extract_textures(&mut data, &texture_output)?;
-        }
-
-        let mut file = BufWriter::new(File::create(out_file)?);
-
-        match format {
-          Format::Json => serde_json::to_writer_pretty(&mut file, &data)?,
-          Format::Yaml => serde_yaml::to_writer(&mut file, &data)?,
-        }
+        let extraction_rules = with_texture_rule(extraction_rules, unpack_textures);
+        decode_file(&in_file, &out_file, format, extraction_rules, png, true)?;
       }
       Command::Encode {
         in_file,
@@ -113,22 +278,128 @@ impl OctModule {
         endian,
         format,
         repack_textures,
+        extraction_rules,
+        schema,
       } => {
-        let file = BufReader::new(File::open(&in_file)?);
-        let mut data = match format {
-          Format::Json => serde_json::from_reader(file)?,
-          Format::Yaml => serde_yaml::from_reader(file)?,
-        };
+        let extraction_rules = with_texture_rule(extraction_rules, repack_textures);
+        let schema = schema.map(|schema| read_schema(&schema)).transpose()?;
+
+        encode_file(
+          &in_file,
+          &out_file,
+          endian,
+          format,
+          extraction_rules,
+          schema.as_ref(),
+          true,
+        )?;
+      }
+      Command::DecodeDir {
+        in_dir,
+        out_dir,
+        format,
+        unpack_textures,
+        extraction_rules,
+        png,
+      } => {
+        let extraction_rules = with_texture_rule(extraction_rules, unpack_textures);
+        decode_dir(&in_dir, &out_dir, format, extraction_rules, png)?;
+      }
+      Command::EncodeDir {
+        in_dir,
+        out_dir,
+        endian,
+        repack_textures,
+        extraction_rules,
+        schema,
+      } => {
+        let extraction_rules = with_texture_rule(extraction_rules, repack_textures);
+        let schema = schema.map(|schema| read_schema(&schema)).transpose()?;
+        encode_dir(&in_dir, &out_dir, endian, extraction_rules, schema.as_ref())?;
+      }
+      Command::Schema {
+        in_files,
+        out_file,
+        json_schema,
+      } => {
+        let mut files = Vec::new();
+        for in_file in in_files {
+          collect_files(&in_file, &mut files)?;
+        }
+
+        let mut schema = Schema::default();
+        for file in files {
+          match matryoshka::decode(&mut BufReader::new(File::open(&file)?)) {
+            Ok((data, _)) => schema.add(&data),
+            Err(err) => println!("Skipping {}: {err}", file.to_string_lossy()),
+          }
+        }
 
-        if repack_textures {
-          let texture_input = in_file.with_extension("textures");
+        let file = BufWriter::new(File::create(out_file)?);
+        if json_schema {
+          serde_json::to_writer_pretty(file, &schema::to_json_schema(&schema))?;
+        } else {
+          serde_json::to_writer_pretty(file, &schema)?;
+        }
+      }
+      Command::Diff { old_file, new_file } => {
+        let (old, _) = matryoshka::decode(&mut BufReader::new(File::open(old_file)?))?;
+        let (new, _) = matryoshka::decode(&mut BufReader::new(File::open(new_file)?))?;
 
-          println!("Loading textures from: {}", texture_input.to_string_lossy());
-          find_and_set_textures(&mut data, &texture_input)?;
+        for difference in matryoshka::diff(&old, &new) {
+          match difference.change {
+            Change::Added(data) => {
+              println!("{} {}: {}", "+".green(), difference.path, describe(data)?)
+            }
+            Change::Removed(data) => {
+              println!("{} {}: {}", "-".red(), difference.path, describe(data)?)
+            }
+            Change::Modified(old, new) => println!(
+              "{} {}: {} -> {}",
+              "~".yellow(),
+              difference.path,
+              describe(old)?,
+              describe(new)?
+            ),
+          }
         }
+      }
+      Command::Merge {
+        base_file,
+        ours_file,
+        theirs_file,
+        out_file,
+      } => {
+        let (base, _) = matryoshka::decode(&mut BufReader::new(File::open(base_file)?))?;
+        let (ours, endian) = matryoshka::decode(&mut BufReader::new(File::open(ours_file)?))?;
+        let (theirs, _) = matryoshka::decode(&mut BufReader::new(File::open(theirs_file)?))?;
+
+        let merged = matryoshka::merge(&base, &ours, &theirs);
 
         let mut file = BufWriter::new(File::create(out_file)?);
-        matryoshka::encode(&mut file, data, endian.into())?;
+        matryoshka::encode(&mut file, merged.data, endian)?;
+
+        if !merged.conflicts.is_empty() {
+          for conflict in &merged.conflicts {
+            println!("{} {}", "Conflict:".red().bold(), conflict.path);
+            for (side, data) in [
+              ("base", &conflict.base),
+              ("ours", &conflict.ours),
+              ("theirs", &conflict.theirs),
+            ] {
+              let data = match data {
+                Some(data) => describe_container(data)?,
+                None => "<missing>".to_string(),
+              };
+              println!("  {side:<6} {data}");
+            }
+          }
+
+          return Err(anyhow!(
+            "{} conflicts, kept our side for each of them",
+            merged.conflicts.len()
+          ));
+        }
       }
     }
 
@@ -136,82 +407,450 @@ impl OctModule {
   }
 }
 
-const TEXTURE_PREFIX: &str = "Texture#";
-const PATH_KEY: &str = "SourceFilePath";
-const DATA_KEY: &str = "Data";
-const DDS: &str = "dds";
+fn with_texture_rule(
+  mut extraction_rules: Vec<ExtractionRule>,
+  textures: bool,
+) -> Vec<ExtractionRule> {
+  if textures && !extraction_rules.contains(&ExtractionRule::texture()) {
+    extraction_rules.push(ExtractionRule::texture());
+  }
+  extraction_rules
+}
 
-fn find_and_extract_textures(
-  data: &mut IndexMap<String, ContainerData>,
-  output_path: &Path,
+fn read_schema(path: &Path) -> anyhow::Result<Schema> {
+  Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn decode_file(
+  in_file: &Path,
+  out_file: &Path,
+  format: Option<Format>,
+  extraction_rules: Vec<ExtractionRule>,
+  png: bool,
+  verbose: bool,
 ) -> anyhow::Result<()> {
-  for (key, data) in data {
-    match data {
-      ContainerData::Single(Data::Container(container)) => {
-        if key.starts_with(TEXTURE_PREFIX) {
-          if let (
-            Some(ContainerData::Single(Data::String(path))),
-            Some(ContainerData::Single(Data::Binary(data))),
-          ) = (container.get(PATH_KEY), container.get(DATA_KEY))
-          {
-            let out = output_path
-              .join(path.replace('\\', std::path::MAIN_SEPARATOR_STR))
-              .with_extension(DDS);
-            println!("Extracting: {}", out.to_string_lossy());
-
-            if let Some(parent) = out.parent() {
-              if !parent.exists() {
-                fs::create_dir_all(parent)?;
-              }
-            }
+  let mut file = BufReader::new(File::open(in_file)?);
 
-            let mut texture_file = File::create(&out)?;
-            texture_file.write_all(data)?;
+  let (mut data, endian) = matryoshka::decode(&mut file)?;
+  if verbose {
+    println!("Read file with endian: {}", endian);
+  }
 
-            *container.get_mut(DATA_KEY).unwrap() = ContainerData::Single(Data::String(format!(
-              "file:{}",
-              out.strip_prefix(output_path)?.to_string_lossy()
-            )));
-            continue;
-          }
-        }
+  let format = format
+    .or_else(|| Format::from_path(out_file))
+    .unwrap_or(Format::Json);
 
-        find_and_extract_textures(container, output_path)?;
+  if !extraction_rules.is_empty() {
+    let assets_output = out_file.with_extension(ASSETS_EXTENSION);
+
+    let extracted = find_and_extract_files(&mut data, &extraction_rules, &assets_output, png)?;
+    if verbose {
+      println!("Extracted files to: {}", assets_output.to_string_lossy());
+      for file in extracted {
+        println!("Extracted: {}", file.to_string_lossy());
       }
-      ContainerData::Single(_) => {}
-      _ => unimplemented!("find_and_extract_textures: Multiple container data is not a thing."),
     }
   }
 
-  Ok(())
+  let mut file = BufWriter::new(File::create(out_file)?);
+  format.write(&mut file, &data)?;
+
+  Metadata {
+    endian: endian.into(),
+    format,
+    extraction_rules,
+  }
+  .write(out_file)
 }
 
-fn find_and_set_textures(
-  data: &mut IndexMap<String, ContainerData>,
-  input_path: &Path,
+fn encode_file(
+  in_file: &Path,
+  out_file: &Path,
+  endian: Option<Endian>,
+  format: Option<Format>,
+  mut extraction_rules: Vec<ExtractionRule>,
+  schema: Option<&Schema>,
+  verbose: bool,
 ) -> anyhow::Result<()> {
-  for data in data.values_mut() {
-    match data {
-      ContainerData::Multiple(_) => {
-        unimplemented!("find_and_set_textures: Multiple container data is not a thing.")
-      }
-      ContainerData::Single(Data::Container(container)) => {
-        find_and_set_textures(container, input_path)?;
+  let metadata = Metadata::read(in_file)?;
+
+  let format = format
+    .or_else(|| Format::from_path(in_file))
+    .or_else(|| metadata.as_ref().map(|metadata| metadata.format.clone()))
+    .unwrap_or(Format::Json);
+
+  if let Some(metadata) = &metadata {
+    for rule in &metadata.extraction_rules {
+      if !extraction_rules.contains(rule) {
+        extraction_rules.push(rule.clone());
       }
-      ContainerData::Single(Data::String(string_content)) => {
-        if let Some(file_name) = string_content.strip_prefix("file:") {
-          let path = input_path.join(file_name);
-          println!("Embedding: {}", path.to_string_lossy());
-          let mut texture_file = File::open(&path)?;
-          let mut texture_buf = Vec::new();
-          texture_file.read_to_end(&mut texture_buf)?;
-
-          *data = ContainerData::Single(Data::Binary(texture_buf));
-        }
+    }
+  }
+
+  let endian = endian
+    .or_else(|| metadata.map(|metadata| metadata.endian))
+    .unwrap_or(Endian::Little);
+  if verbose {
+    println!("Writing {format} with endian: {endian}");
+  }
+
+  let file = BufReader::new(File::open(in_file)?);
+  let mut data = format.read(file)?;
+
+  if !extraction_rules.is_empty() {
+    let assets_input = assets_path(in_file);
+
+    let embedded = find_and_embed_files(&mut data, &extraction_rules, &assets_input)?;
+    if verbose {
+      println!("Loaded files from: {}", assets_input.to_string_lossy());
+      for file in embedded {
+        println!("Embedded: {}", file.to_string_lossy());
       }
-      _ => {}
     }
   }
 
+  if let Some(schema) = schema {
+    let violations = schema.validate(&data);
+    if !violations.is_empty() {
+      let violations: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+      return Err(anyhow!(
+        "{} nodes don't match the schema, nothing was written:\n  {}",
+        violations.len(),
+        violations.join("\n  ")
+      ));
+    }
+  }
+
+  let mut file = BufWriter::new(File::create(out_file)?);
+  matryoshka::encode(&mut file, data, endian.into())
+}
+
+/// Decodes every Tupperware file below `in_dir` to the same path below `out_dir`, with the extension
+/// of `format` appended.
+fn decode_dir(
+  in_dir: &Path,
+  out_dir: &Path,
+  format: Format,
+  extraction_rules: Vec<ExtractionRule>,
+  png: bool,
+) -> anyhow::Result<()> {
+  let mut files = Vec::new();
+  collect_files(in_dir, &mut files)?;
+
+  // anything in a game dump that isn't Tupperware is left alone
+  let mut tupperware = Vec::new();
+  for file in files {
+    if matryoshka::is_tupperware(&mut BufReader::new(File::open(&file)?))? {
+      tupperware.push(file);
+    }
+  }
+
+  run_batch(tupperware, |in_file| {
+    // keeps the original extension, so encode-dir can restore it
+    let mut out_file = out_dir.join(in_file.strip_prefix(in_dir)?).into_os_string();
+    out_file.push(format!(".{format}"));
+    let out_file = PathBuf::from(out_file);
+
+    if let Some(parent) = out_file.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    decode_file(
+      in_file,
+      &out_file,
+      Some(format.clone()),
+      extraction_rules.clone(),
+      png,
+      false,
+    )
+  })
+}
+
+/// Encodes the files [`decode_dir`] wrote below `in_dir` back to their original path below
+/// `out_dir`.
+fn encode_dir(
+  in_dir: &Path,
+  out_dir: &Path,
+  endian: Option<Endian>,
+  extraction_rules: Vec<ExtractionRule>,
+  schema: Option<&Schema>,
+) -> anyhow::Result<()> {
+  let mut files = Vec::new();
+  collect_files(in_dir, &mut files)?;
+
+  // decode-dir writes names like `a.oct.json`
+  let files = files
+    .into_iter()
+    .filter(|file| {
+      let original = file.file_stem().map(Path::new);
+      Format::from_path(file).is_some() && original.and_then(Path::extension).is_some()
+    })
+    .collect();
+
+  run_batch(files, |in_file| {
+    let out_file = out_dir.join(in_file.strip_prefix(in_dir)?.with_extension(""));
+
+    if let Some(parent) = out_file.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    encode_file(
+      in_file,
+      &out_file,
+      endian.clone(),
+      None,
+      extraction_rules.clone(),
+      schema,
+      false,
+    )
+  })
+}
+
+/// Runs `convert` on the files in parallel, then lists the ones that failed.
+fn run_batch<F>(files: Vec<PathBuf>, convert: F) -> anyhow::Result<()>
+where
+  F: Fn(&Path) -> anyhow::Result<()> + Sync,
+{
+  let bar = ProgressBar::new(files.len() as u64);
+
+  let failures: Vec<_> = files
+    .par_iter()
+    .filter_map(|file| {
+      let result = convert(file);
+      bar.inc(1);
+      result.err().map(|err| (file, err))
+    })
+    .collect();
+
+  bar.finish_and_clear();
+
+  println!(
+    "Converted {} of {} files",
+    files.len() - failures.len(),
+    files.len()
+  );
+
+  if failures.is_empty() {
+    return Ok(());
+  }
+
+  println!();
+  for (file, err) in &failures {
+    println!("{} {}: {err}", "!".red(), file.to_string_lossy());
+  }
+
+  Err(anyhow!("{} files failed", failures.len()))
+}
+
+/// Collects `path`, or every file below it if it is a directory, in a stable order.
+pub(crate) fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+  if !path.is_dir() {
+    files.push(path.to_path_buf());
+    return Ok(());
+  }
+
+  let mut entries = std::fs::read_dir(path)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<Vec<_>, _>>()?;
+  entries.sort();
+
+  for entry in entries {
+    collect_files(&entry, files)?;
+  }
+
   Ok(())
 }
+
+fn describe(data: &Data) -> anyhow::Result<String> {
+  Ok(match data {
+    Data::Container(children) => format!("{{{} children}}", children.len()),
+    Data::Binary(data) => format!("<{} bytes>", data.len()),
+    data => serde_json::to_string(data)?,
+  })
+}
+
+fn describe_container(data: &ContainerData) -> anyhow::Result<String> {
+  match data {
+    ContainerData::Single(data) => describe(data),
+    ContainerData::Multiple(list) => Ok(format!(
+      "[{}]",
+      list
+        .iter()
+        .map(describe)
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .join(", ")
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+  use std::path::Path;
+
+  use clap::ValueEnum;
+  use indexmap::IndexMap;
+
+  use matryoshka::{ContainerData, Data};
+
+  use crate::oct::{decode_dir, decode_file, encode_dir, encode_file, Endian, Format, Metadata};
+
+  fn sample() -> IndexMap<String, ContainerData> {
+    let mut wheel = IndexMap::new();
+    wheel.insert(
+      "Radius".to_string(),
+      ContainerData::Single(Data::Float(0.35)),
+    );
+    wheel.insert(
+      "Offset".to_string(),
+      ContainerData::Single(Data::FloatVec(vec![1.1, -2.0, f32::NAN])),
+    );
+
+    let mut car = IndexMap::new();
+    car.insert("Speed".to_string(), ContainerData::Single(Data::Float(1.1)));
+    car.insert(
+      "Wheel".to_string(),
+      ContainerData::Multiple(vec![Data::Container(wheel.clone()), Data::Container(wheel)]),
+    );
+    car.insert(
+      "Name".to_string(),
+      ContainerData::Single(Data::String(" <Lightning & \"McQueen\"> ".to_string())),
+    );
+    car.insert(
+      "Empty".to_string(),
+      ContainerData::Single(Data::String(String::new())),
+    );
+    car.insert(
+      "Body".to_string(),
+      ContainerData::Single(Data::Container(IndexMap::new())),
+    );
+    car.insert("Gear".to_string(), ContainerData::Single(Data::Int(-3)));
+    car.insert(
+      "Gears".to_string(),
+      ContainerData::Single(Data::IntVec(vec![1, 300, -70000])),
+    );
+    car.insert(
+      "Tags".to_string(),
+      ContainerData::Single(Data::StringVec(vec!["fast".to_string(), "".to_string()])),
+    );
+    car.insert(
+      "Data".to_string(),
+      ContainerData::Single(Data::Binary(vec![0, 1, 2, 255])),
+    );
+    car.insert(
+      "Uuid".to_string(),
+      ContainerData::Single(Data::Uuid(uuid::Uuid::from_u128(
+        0x0123_4567_89ab_cdef_0123_4567_89ab_cdef,
+      ))),
+    );
+
+    let mut root = IndexMap::new();
+    root.insert(
+      "Car#McQueen".to_string(),
+      ContainerData::Single(Data::Container(car)),
+    );
+    root
+  }
+
+  fn encode(data: IndexMap<String, ContainerData>) -> Vec<u8> {
+    let mut cursor = Cursor::new(Vec::new());
+    matryoshka::encode(&mut cursor, data, matryoshka::Endian::Little).unwrap();
+    cursor.into_inner()
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let original = encode(sample());
+
+    for format in Format::value_variants() {
+      let (data, _) = matryoshka::decode(&mut Cursor::new(&original)).unwrap();
+
+      let mut text = Vec::new();
+      format.write(&mut text, &data).unwrap();
+      let data = format.read(text.as_slice()).unwrap();
+
+      assert!(encode(data) == original, "{format} does not round-trip");
+    }
+  }
+
+  #[test]
+  fn test_format_from_path() {
+    let format = |path: &str| Format::from_path(Path::new(path)).map(|format| format.to_string());
+
+    assert_eq!(format("car.json").as_deref(), Some("json"));
+    assert_eq!(format("car.YML").as_deref(), Some("yaml"));
+    assert_eq!(format("dir.toml/car.ron").as_deref(), Some("ron"));
+    assert_eq!(format("car.xml").as_deref(), Some("xml"));
+    assert_eq!(format("car.txt"), None);
+    assert_eq!(format("car"), None);
+  }
+
+  #[test]
+  fn test_batch() {
+    let dir = std::env::temp_dir().join(format!("offsetting-batch-{}", std::process::id()));
+    let (dump, decoded, encoded) = (dir.join("dump"), dir.join("decoded"), dir.join("encoded"));
+    std::fs::create_dir_all(dump.join("cars")).unwrap();
+
+    let original = encode(sample());
+    std::fs::write(dump.join("cars/mcqueen.tar.oct"), &original).unwrap();
+    // Tupperware by its magic, but cut off
+    std::fs::write(dump.join("broken.oct"), &original[..12]).unwrap();
+    std::fs::write(dump.join("readme.txt"), "not Tupperware").unwrap();
+
+    let err = decode_dir(&dump, &decoded, Format::Yaml, vec![], false).unwrap_err();
+    assert_eq!(err.to_string(), "1 files failed");
+    assert!(decoded.join("cars/mcqueen.tar.oct.yaml").is_file());
+    assert!(!decoded.join("broken.oct.yaml").exists());
+    assert!(!decoded.join("readme.txt.yaml").exists());
+
+    std::fs::write(decoded.join("broken.oct.json"), "{").unwrap();
+    let err = encode_dir(&decoded, &encoded, None, vec![], None).unwrap_err();
+    assert_eq!(err.to_string(), "1 files failed");
+    // only the format extension is removed from `mcqueen.tar.oct.yaml`
+    assert!(std::fs::read(encoded.join("cars/mcqueen.tar.oct")).unwrap() == original);
+    assert!(!encoded.join("broken.oct").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_metadata_round_trip() {
+    let dir = std::env::temp_dir().join(format!("offsetting-metadata-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut original = Cursor::new(Vec::new());
+    matryoshka::encode(&mut original, sample(), matryoshka::Endian::Big).unwrap();
+    let original = original.into_inner();
+    let oct = dir.join("car.oct");
+    std::fs::write(&oct, &original).unwrap();
+
+    // the extension doesn't name a format, so the sidecar has to
+    let decoded = dir.join("car.txt");
+    decode_file(&oct, &decoded, Some(Format::Yaml), vec![], false, false).unwrap();
+    let metadata = Metadata::read(&decoded).unwrap().unwrap();
+    assert!(matches!(metadata.endian, Endian::Big));
+    assert!(matches!(metadata.format, Format::Yaml));
+
+    let encoded = dir.join("car_encoded.oct");
+    encode_file(&decoded, &encoded, None, None, vec![], None, false).unwrap();
+    assert!(std::fs::read(&encoded).unwrap() == original);
+
+    // without the sidecar it falls back to json
+    let decoded = dir.join("car.data");
+    decode_file(&oct, &decoded, None, vec![], false, false).unwrap();
+    std::fs::remove_file(Metadata::path(&decoded)).unwrap();
+    encode_file(
+      &decoded,
+      &encoded,
+      Some(Endian::Big),
+      None,
+      vec![],
+      None,
+      false,
+    )
+    .unwrap();
+    assert!(std::fs::read(&encoded).unwrap() == original);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
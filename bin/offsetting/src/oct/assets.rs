@@ -0,0 +1,415 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+use matryoshka::{ContainerData, Data};
+
+use crate::oct::texture::{read_png, DecodedTexture, DDS_EXTENSION, PNG_EXTENSION};
+
+pub(super) const ASSETS_EXTENSION: &str = "assets";
+/// Directory older versions extracted textures to.
+const LEGACY_ASSETS_EXTENSION: &str = "textures";
+const FILE_PREFIX: &str = "file:";
+
+/// Moves the binary `data_field` of every container whose key matches `key` into its own file.
+///
+/// The file is named after the `path_field` string of the container, or after the container key
+/// if there is none, and gets `extension` appended.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct ExtractionRule {
+  pub(super) key: String,
+  pub(super) path_field: Option<String>,
+  pub(super) data_field: String,
+  pub(super) extension: String,
+}
+
+impl ExtractionRule {
+  pub(super) fn texture() -> Self {
+    Self {
+      key: "Texture#*".to_string(),
+      path_field: Some("SourceFilePath".to_string()),
+      data_field: "Data".to_string(),
+      extension: "dds".to_string(),
+    }
+  }
+
+  fn matches(&self, key: &str) -> bool {
+    matches_pattern(&self.key, key)
+  }
+}
+
+/// Parses `KEY:PATH_FIELD:DATA_FIELD:EXTENSION`, where `PATH_FIELD` may be left empty.
+impl FromStr for ExtractionRule {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parts: Vec<_> = s.split(':').collect();
+    let [key, path_field, data_field, extension] = parts[..] else {
+      return Err("Expected KEY:PATH_FIELD:DATA_FIELD:EXTENSION".to_string());
+    };
+
+    if key.is_empty() || data_field.is_empty() || extension.is_empty() {
+      return Err("Only PATH_FIELD may be empty".to_string());
+    }
+
+    Ok(Self {
+      key: key.to_string(),
+      path_field: (!path_field.is_empty()).then(|| path_field.to_string()),
+      data_field: data_field.to_string(),
+      extension: extension.to_string(),
+    })
+  }
+}
+
+/// Matches `key` against a pattern in which `*` stands for any number of characters.
+fn matches_pattern(pattern: &str, key: &str) -> bool {
+  match pattern.split_once('*') {
+    None => pattern == key,
+    Some((prefix, rest)) => {
+      let Some(key) = key.strip_prefix(prefix) else {
+        return false;
+      };
+      (0..=key.len())
+        .filter(|i| key.is_char_boundary(*i))
+        .any(|i| matches_pattern(rest, &key[i..]))
+    }
+  }
+}
+
+/// Finds the directory the files referenced by `file` were extracted to.
+pub(super) fn assets_path(file: &Path) -> PathBuf {
+  let path = file.with_extension(ASSETS_EXTENSION);
+  let legacy_path = file.with_extension(LEGACY_ASSETS_EXTENSION);
+
+  if !path.exists() && legacy_path.exists() {
+    legacy_path
+  } else {
+    path
+  }
+}
+
+/// Extracts the files matched by `rules`, converting dds textures to png if `textures_as_png`.
+/// Textures of formats that can't be converted are extracted as dds.
+///
+/// Files that would end up at the same path, e.g. repeated siblings with the same source path, get
+/// a `_<n>` suffix in the order they appear. The file reference stored in the data records the
+/// final name, so embedding them again maps every file back to the node it was extracted from.
+///
+/// Returns the extracted files.
+pub(super) fn find_and_extract_files(
+  data: &mut IndexMap<String, ContainerData>,
+  rules: &[ExtractionRule],
+  output_path: &Path,
+  textures_as_png: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+  let mut extractor = Extractor {
+    rules,
+    output_path,
+    textures_as_png,
+    extracted: IndexSet::new(),
+  };
+
+  extractor.find_and_extract_files(data)?;
+  Ok(extractor.extracted.into_iter().collect())
+}
+
+struct Extractor<'a> {
+  rules: &'a [ExtractionRule],
+  output_path: &'a Path,
+  textures_as_png: bool,
+  extracted: IndexSet<PathBuf>,
+}
+
+impl Extractor<'_> {
+  fn find_and_extract_files(
+    &mut self,
+    data: &mut IndexMap<String, ContainerData>,
+  ) -> anyhow::Result<()> {
+    for (key, data) in data {
+      for data in data.as_mut_slice() {
+        let Data::Container(container) = data else {
+          continue;
+        };
+
+        if let Some(rule) = self.rules.iter().find(|rule| rule.matches(key)) {
+          if self.extract_file(key, container, rule)? {
+            continue;
+          }
+        }
+
+        self.find_and_extract_files(container)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn extract_file(
+    &mut self,
+    key: &str,
+    container: &mut IndexMap<String, ContainerData>,
+    rule: &ExtractionRule,
+  ) -> anyhow::Result<bool> {
+    let Some(ContainerData::Single(Data::Binary(data))) = container.get(&rule.data_field) else {
+      return Ok(false);
+    };
+
+    let name = match &rule.path_field {
+      Some(path_field) => match container.get(path_field) {
+        Some(ContainerData::Single(Data::String(path))) => path.as_str(),
+        _ => return Ok(false),
+      },
+      None => key.split_once('#').map_or(key, |(_, name)| name),
+    };
+
+    let texture = if self.textures_as_png && rule.extension == DDS_EXTENSION {
+      DecodedTexture::decode(data)
+    } else {
+      None
+    };
+    let extension = match texture {
+      Some(_) => PNG_EXTENSION,
+      None => &rule.extension,
+    };
+
+    let out = self.unique_path(
+      self
+        .output_path
+        .join(relative_path(name))
+        .with_extension(extension),
+    );
+
+    if let Some(parent) = out.parent() {
+      if !parent.exists() {
+        fs::create_dir_all(parent)?;
+      }
+    }
+
+    match texture {
+      Some(texture) => texture.write_png(&out)?,
+      None => {
+        let mut file = File::create(&out)?;
+        file.write_all(data)?;
+      }
+    }
+
+    *container.get_mut(&rule.data_field).unwrap() = ContainerData::Single(Data::String(format!(
+      "{FILE_PREFIX}{}",
+      out.strip_prefix(self.output_path)?.to_string_lossy()
+    )));
+
+    Ok(true)
+  }
+
+  /// Appends `_<n>` to the file name until it doesn't clash with an already extracted file.
+  fn unique_path(&mut self, path: PathBuf) -> PathBuf {
+    let mut unique = path.clone();
+    let mut n = 0;
+    while self.extracted.contains(&unique) {
+      n += 1;
+      let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+      let mut name = format!("{stem}_{n}");
+      if let Some(extension) = path.extension() {
+        name = format!("{name}.{}", extension.to_string_lossy());
+      }
+      unique = path.with_file_name(name);
+    }
+
+    self.extracted.insert(unique.clone());
+    unique
+  }
+}
+
+/// `name` as a path below the assets folder, without the drive, root and `..` parts that would
+/// lead out of it, as the names come from the file.
+fn relative_path(name: &str) -> PathBuf {
+  let path: PathBuf = name
+    .split(['/', '\\'])
+    .filter(|part| !matches!(*part, "" | "." | "..") && !part.contains(':'))
+    .collect();
+
+  if path.as_os_str().is_empty() {
+    PathBuf::from("unnamed")
+  } else {
+    path
+  }
+}
+
+/// Replaces the file references written by [`find_and_extract_files`], returning the embedded files.
+pub(super) fn find_and_embed_files(
+  data: &mut IndexMap<String, ContainerData>,
+  rules: &[ExtractionRule],
+  input_path: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+  let mut embedded = Vec::new();
+  embed_files(data, rules, input_path, &mut embedded)?;
+  Ok(embedded)
+}
+
+fn embed_files(
+  data: &mut IndexMap<String, ContainerData>,
+  rules: &[ExtractionRule],
+  input_path: &Path,
+  embedded: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+  for (key, data) in data {
+    for data in data.as_mut_slice() {
+      let Data::Container(container) = data else {
+        continue;
+      };
+
+      if let Some(rule) = rules.iter().find(|rule| rule.matches(key)) {
+        if let Some(path) = embed_file(container, rule, input_path)? {
+          embedded.push(path);
+          continue;
+        }
+      }
+
+      embed_files(container, rules, input_path, embedded)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn embed_file(
+  container: &mut IndexMap<String, ContainerData>,
+  rule: &ExtractionRule,
+  input_path: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+  let Some(data) = container.get_mut(&rule.data_field) else {
+    return Ok(None);
+  };
+
+  let ContainerData::Single(Data::String(reference)) = data else {
+    return Ok(None);
+  };
+
+  let Some(file_name) = reference.strip_prefix(FILE_PREFIX) else {
+    return Ok(None);
+  };
+
+  let path = input_path.join(file_name);
+
+  let is_png = path
+    .extension()
+    .is_some_and(|extension| extension == PNG_EXTENSION);
+
+  let buf = if rule.extension == DDS_EXTENSION && is_png {
+    read_png(&path)?
+  } else {
+    let mut file =
+      File::open(&path).map_err(|err| anyhow!("Can't open {}: {err}", path.to_string_lossy()))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    buf
+  };
+
+  *data = ContainerData::Single(Data::Binary(buf));
+
+  Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+  use indexmap::IndexMap;
+  use matryoshka::{ContainerData, Data};
+
+  use crate::oct::assets::{
+    find_and_embed_files, find_and_extract_files, matches_pattern, relative_path, ExtractionRule,
+  };
+
+  #[test]
+  fn test_matches_pattern() {
+    assert!(matches_pattern("Texture#*", "Texture#a\\b.tga"));
+    assert!(matches_pattern("Texture#*", "Texture#"));
+    assert!(!matches_pattern("Texture#*", "Textures#a"));
+    assert!(matches_pattern("*Buffer#*", "VertexBuffer#0"));
+    assert!(matches_pattern("Shader", "Shader"));
+    assert!(!matches_pattern("Shader", "Shader#a"));
+  }
+
+  #[test]
+  fn test_relative_path() {
+    let path = |name| relative_path(name).to_string_lossy().replace('\\', "/");
+
+    assert_eq!(path("art\\a.tga"), "art/a.tga");
+    assert_eq!(path("..\\..\\windows\\a.tga"), "windows/a.tga");
+    assert_eq!(path("C:\\art\\.\\a.tga"), "art/a.tga");
+    assert_eq!(path("/etc/a"), "etc/a");
+    assert_eq!(path("D:art/a.tga"), "a.tga");
+    assert_eq!(path(".."), "unnamed");
+  }
+
+  #[test]
+  fn test_parse_rule() {
+    assert_eq!(
+      "Texture#*:SourceFilePath:Data:dds".parse(),
+      Ok(ExtractionRule::texture())
+    );
+
+    let rule: ExtractionRule = "VertexBuffer#*::Data:vb".parse().unwrap();
+    assert_eq!(rule.path_field, None);
+
+    assert!("Texture#*:Data:dds".parse::<ExtractionRule>().is_err());
+    assert!("Texture#*:SourceFilePath::dds"
+      .parse::<ExtractionRule>()
+      .is_err());
+  }
+
+  #[test]
+  fn test_multiple_round_trip() {
+    let texture = |data: u8| {
+      Data::Container(IndexMap::from([
+        (
+          "SourceFilePath".to_string(),
+          ContainerData::Single(Data::String("art\\a.tga".to_string())),
+        ),
+        (
+          "Data".to_string(),
+          ContainerData::Single(Data::Binary(vec![data])),
+        ),
+      ]))
+    };
+
+    let original = IndexMap::from([(
+      "Texture#0".to_string(),
+      ContainerData::Multiple(vec![texture(1), texture(2), texture(3)]),
+    )]);
+
+    let path = std::env::temp_dir().join(format!("offsetting-assets-{}", std::process::id()));
+    let rules = [ExtractionRule::texture()];
+
+    // the data isn't a dds that can be converted to png, so it's extracted as is either way
+    for textures_as_png in [false, true] {
+      let mut data = original.clone();
+      find_and_extract_files(&mut data, &rules, &path, textures_as_png).unwrap();
+
+      let references: Vec<_> = data["Texture#0"]
+        .as_slice()
+        .iter()
+        .map(|texture| match texture {
+          Data::Container(container) => match &container["Data"] {
+            ContainerData::Single(Data::String(reference)) => reference.replace('\\', "/"),
+            _ => panic!("Data was not replaced by a reference"),
+          },
+          _ => unreachable!(),
+        })
+        .collect();
+      assert_eq!(
+        references,
+        ["file:art/a.dds", "file:art/a_1.dds", "file:art/a_2.dds"]
+      );
+
+      find_and_embed_files(&mut data, &rules, &path).unwrap();
+      std::fs::remove_dir_all(&path).unwrap();
+
+      assert!(matryoshka::diff(&original, &data).is_empty());
+    }
+  }
+}
@@ -0,0 +1,95 @@
+use serde_json::{json, Map, Value};
+
+use matryoshka::{DataType, Schema, SchemaField, SchemaNode};
+
+/// Describes the json files `oct decode` writes for data matching `schema`.
+pub(super) fn to_json_schema(schema: &Schema) -> Value {
+  let mut root = node_schema(&schema.root);
+  root["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+  root
+}
+
+fn node_schema(node: &SchemaNode) -> Value {
+  let mut schemas: Vec<_> = node
+    .types
+    .iter()
+    .map(|data_type| type_schema(data_type, node))
+    .collect();
+
+  match schemas.len() {
+    1 => schemas.remove(0),
+    _ => json!({ "anyOf": schemas }),
+  }
+}
+
+fn field_schema(field: &SchemaField) -> Value {
+  let schema = node_schema(&field.node);
+
+  // repeated siblings are written as a list of their values
+  if field.repeated {
+    json!({ "anyOf": [schema.clone(), { "type": "array", "items": schema }] })
+  } else {
+    schema
+  }
+}
+
+fn type_schema(data_type: &DataType, node: &SchemaNode) -> Value {
+  match data_type {
+    DataType::Container => {
+      let mut properties = Map::new();
+      let mut pattern_properties = Map::new();
+      // keys with an id can't be required by name
+      let mut required = Vec::new();
+
+      for (key, field) in &node.children {
+        match key.strip_suffix("#*") {
+          Some(kind) => {
+            let pattern = format!("^{}#", escape_regex(kind));
+            pattern_properties.insert(pattern, field_schema(field));
+          }
+          None => {
+            properties.insert(key.clone(), field_schema(field));
+            if !field.optional {
+              required.push(key.clone());
+            }
+          }
+        }
+      }
+
+      let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+      });
+      if !pattern_properties.is_empty() {
+        schema["patternProperties"] = Value::Object(pattern_properties);
+      }
+      if !required.is_empty() {
+        schema["required"] = json!(required);
+      }
+      schema
+    }
+    DataType::Binary => json!({ "type": "string", "pattern": "^(base64|file):" }),
+    DataType::Uuid => json!({ "type": "string", "format": "uuid" }),
+    DataType::Int => json!({ "type": "integer" }),
+    DataType::IntVec => json!({ "type": "array", "items": { "type": "integer" } }),
+    // NaN is written as null
+    DataType::Float => json!({ "type": ["number", "null"] }),
+    DataType::FloatVec => {
+      json!({ "type": "array", "items": { "type": ["number", "null"] } })
+    }
+    DataType::String => json!({ "type": "string" }),
+    DataType::StringVec => json!({ "type": "array", "items": { "type": "string" } }),
+  }
+}
+
+fn escape_regex(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    if "\\.+*?()|[]{}^$".contains(c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use png::{BitDepth, ColorType, Transformations};
+use serde::{Deserialize, Serialize};
+
+use x_flipper_360::{decode_dds, encode_dds, Image};
+
+pub(super) const DDS_EXTENSION: &str = "dds";
+pub(super) const PNG_EXTENSION: &str = "png";
+const METADATA_EXTENSION: &str = "meta";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DdsFormat {
+  Dxt1,
+  Dxt3,
+  Dxt5,
+  Rgba8,
+}
+
+impl From<x_flipper_360::Format> for DdsFormat {
+  fn from(value: x_flipper_360::Format) -> Self {
+    match value {
+      x_flipper_360::Format::Dxt1 => Self::Dxt1,
+      x_flipper_360::Format::Dxt3 => Self::Dxt3,
+      x_flipper_360::Format::Dxt5 => Self::Dxt5,
+      x_flipper_360::Format::RGBA8 => Self::Rgba8,
+    }
+  }
+}
+
+impl From<DdsFormat> for x_flipper_360::Format {
+  fn from(value: DdsFormat) -> Self {
+    match value {
+      DdsFormat::Dxt1 => Self::Dxt1,
+      DdsFormat::Dxt3 => Self::Dxt3,
+      DdsFormat::Dxt5 => Self::Dxt5,
+      DdsFormat::Rgba8 => Self::RGBA8,
+    }
+  }
+}
+
+/// Written next to a png, so it can be compressed back into the dds it was converted from.
+#[derive(Serialize, Deserialize)]
+struct TextureMetadata {
+  format: DdsFormat,
+  mipmap_levels: u32,
+}
+
+fn metadata_path(png: &Path) -> PathBuf {
+  png.with_extension(METADATA_EXTENSION)
+}
+
+/// The top mip level of a dds, with what is needed to compress it back.
+pub(super) struct DecodedTexture {
+  image: Image,
+  metadata: TextureMetadata,
+}
+
+impl DecodedTexture {
+  /// Decodes `dds`, or returns `None` if its format can't be converted, so it's extracted as is.
+  pub(super) fn decode(dds: &[u8]) -> Option<Self> {
+    let (image, format, mipmap_levels) = decode_dds(&mut &dds[..]).ok()?;
+
+    Some(Self {
+      image,
+      metadata: TextureMetadata {
+        format: format.into(),
+        mipmap_levels,
+      },
+    })
+  }
+
+  /// Writes the image to `png`, and the metadata [`read_png`] compresses it back with next to it.
+  pub(super) fn write_png(&self, png: &Path) -> anyhow::Result<()> {
+    let mut encoder = png::Encoder::new(
+      BufWriter::new(File::create(png)?),
+      self.image.width,
+      self.image.height,
+    );
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&self.image.data)?;
+
+    serde_json::to_writer_pretty(File::create(metadata_path(png))?, &self.metadata)?;
+
+    Ok(())
+  }
+}
+
+/// Compresses a png to a dds with the format and mipmap levels recorded by [`DecodedTexture::write_png`].
+pub(super) fn read_png(png: &Path) -> anyhow::Result<Vec<u8>> {
+  let path = metadata_path(png);
+  let metadata: TextureMetadata = serde_json::from_reader(BufReader::new(
+    File::open(&path).map_err(|err| anyhow!("Can't open {}: {err}", path.to_string_lossy()))?,
+  ))?;
+
+  let mut decoder = png::Decoder::new(BufReader::new(File::open(png)?));
+  decoder.set_transformations(Transformations::normalize_to_color8());
+  let mut reader = decoder.read_info()?;
+
+  let mut buf = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut buf)?;
+  let pixels = &buf[..info.buffer_size()];
+
+  let data = match info.color_type {
+    ColorType::Rgba => pixels.to_vec(),
+    ColorType::Rgb => pixels
+      .chunks_exact(3)
+      .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+      .collect(),
+    ColorType::GrayscaleAlpha => pixels
+      .chunks_exact(2)
+      .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+      .collect(),
+    ColorType::Grayscale => pixels.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+    ColorType::Indexed => unreachable!("indexed colors are expanded"),
+  };
+
+  let image = Image {
+    width: info.width,
+    height: info.height,
+    data,
+  };
+
+  let mut dds = Vec::new();
+  encode_dds(
+    &image,
+    metadata.format.into(),
+    metadata.mipmap_levels,
+    &mut dds,
+  )?;
+
+  Ok(dds)
+}
@@ -0,0 +1,93 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+use toml_edit::ser::ValueSerializer;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, InlineTable, Item, Table, Value};
+
+use matryoshka::{ContainerData, Data};
+
+/// Builds a TOML document that keeps the node order of `data`.
+///
+/// TOML places sub-tables after all plain values of a table, so containers that are followed by
+/// a value are written as inline tables instead.
+pub(super) fn to_document(data: &IndexMap<String, ContainerData>) -> anyhow::Result<DocumentMut> {
+  let mut document = DocumentMut::new();
+  fill_table(document.as_table_mut(), data)?;
+  Ok(document)
+}
+
+fn fill_table(table: &mut Table, data: &IndexMap<String, ContainerData>) -> anyhow::Result<()> {
+  let last_value = data.values().rposition(|data| !is_table(data));
+
+  for (index, (key, data)) in data.iter().enumerate() {
+    let inline = last_value.is_some_and(|last_value| index < last_value);
+
+    let item = match data {
+      ContainerData::Single(Data::Container(children)) if !inline => {
+        let mut table = Table::new();
+        fill_table(&mut table, children)?;
+        Item::Table(table)
+      }
+      ContainerData::Multiple(list) if !inline && is_table(data) => {
+        let mut tables = ArrayOfTables::new();
+        for data in list {
+          let Data::Container(children) = data else {
+            unreachable!()
+          };
+          let mut table = Table::new();
+          fill_table(&mut table, children)?;
+          tables.push(table);
+        }
+        Item::ArrayOfTables(tables)
+      }
+      data => Item::Value(container_value(data)?),
+    };
+
+    table.insert(key, item);
+  }
+
+  Ok(())
+}
+
+fn is_table(data: &ContainerData) -> bool {
+  match data {
+    ContainerData::Single(data) => matches!(data, Data::Container(_)),
+    ContainerData::Multiple(list) => {
+      !list.is_empty() && list.iter().all(|data| matches!(data, Data::Container(_)))
+    }
+  }
+}
+
+fn container_value(data: &ContainerData) -> anyhow::Result<Value> {
+  match data {
+    ContainerData::Single(data) => value(data),
+    ContainerData::Multiple(list) => Ok(Value::Array(
+      list.iter().map(value).collect::<anyhow::Result<Array>>()?,
+    )),
+  }
+}
+
+fn value(data: &Data) -> anyhow::Result<Value> {
+  Ok(match data {
+    Data::Container(children) => {
+      let mut table = InlineTable::new();
+      for (key, data) in children {
+        table.insert(key, container_value(data)?);
+      }
+      Value::InlineTable(table)
+    }
+    Data::Float(data) => Value::from(float(*data)?),
+    Data::FloatVec(data) => Value::Array(
+      data
+        .iter()
+        .map(|data| float(*data))
+        .collect::<anyhow::Result<Array>>()?,
+    ),
+    data => data.serialize(ValueSerializer::new())?,
+  })
+}
+
+/// Widens to the shortest f64 that reads back as the same f32, so `1.1` isn't written as
+/// `1.100000023841858`.
+fn float(data: f32) -> anyhow::Result<f64> {
+  Ok(data.to_string().parse()?)
+}
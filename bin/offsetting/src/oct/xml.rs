@@ -0,0 +1,262 @@
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose, Engine as _};
+use indexmap::IndexMap;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use uuid::Uuid;
+
+use matryoshka::{ContainerData, Data};
+
+const ROOT: &str = "Tupperware";
+const KEY_ATTRIBUTE: &str = "key";
+const ITEM: &str = "Item";
+
+const CONTAINER: &str = "Container";
+const BINARY: &str = "Binary";
+const UUID: &str = "Uuid";
+const INT: &str = "Int";
+const INT_VEC: &str = "IntVec";
+const FLOAT: &str = "Float";
+const FLOAT_VEC: &str = "FloatVec";
+const STRING: &str = "String";
+const STRING_VEC: &str = "StringVec";
+
+/// Writes every node as an element named after its type, with the node id in the `key` attribute.
+/// Repeated siblings are written as repeated elements.
+pub(super) fn to_writer<W: Write>(
+  write: W,
+  data: &IndexMap<String, ContainerData>,
+) -> anyhow::Result<()> {
+  let mut writer = Writer::new_with_indent(write, b' ', 2);
+
+  writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+  writer.write_event(Event::Start(BytesStart::new(ROOT)))?;
+  write_container(&mut writer, data)?;
+  writer.write_event(Event::End(BytesEnd::new(ROOT)))?;
+
+  Ok(())
+}
+
+fn write_container<W: Write>(
+  writer: &mut Writer<W>,
+  data: &IndexMap<String, ContainerData>,
+) -> anyhow::Result<()> {
+  for (key, data) in data {
+    for data in data.as_slice() {
+      write_data(writer, key, data)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn write_data<W: Write>(writer: &mut Writer<W>, key: &str, data: &Data) -> anyhow::Result<()> {
+  let (name, text) = match data {
+    Data::Container(children) => {
+      let start = BytesStart::new(CONTAINER).with_attributes([(KEY_ATTRIBUTE, key)]);
+      writer.write_event(Event::Start(start))?;
+      write_container(writer, children)?;
+      writer.write_event(Event::End(BytesEnd::new(CONTAINER)))?;
+      return Ok(());
+    }
+    Data::StringVec(items) => {
+      let start = BytesStart::new(STRING_VEC).with_attributes([(KEY_ATTRIBUTE, key)]);
+      writer.write_event(Event::Start(start))?;
+      for item in items {
+        write_text(writer, ITEM, None, item)?;
+      }
+      writer.write_event(Event::End(BytesEnd::new(STRING_VEC)))?;
+      return Ok(());
+    }
+    Data::Binary(data) => (BINARY, general_purpose::STANDARD_NO_PAD.encode(data)),
+    Data::Uuid(uuid) => (UUID, uuid.to_string()),
+    Data::Int(data) => (INT, data.to_string()),
+    Data::IntVec(data) => (INT_VEC, join(data)),
+    Data::Float(data) => (FLOAT, data.to_string()),
+    Data::FloatVec(data) => (FLOAT_VEC, join(data)),
+    Data::String(data) => (STRING, data.clone()),
+  };
+
+  write_text(writer, name, Some(key), &text)
+}
+
+fn write_text<W: Write>(
+  writer: &mut Writer<W>,
+  name: &str,
+  key: Option<&str>,
+  text: &str,
+) -> anyhow::Result<()> {
+  let mut start = BytesStart::new(name);
+  if let Some(key) = key {
+    start.push_attribute((KEY_ATTRIBUTE, key));
+  }
+
+  // an empty start/end pair would get indented, which reads back as whitespace text
+  if text.is_empty() {
+    writer.write_event(Event::Empty(start))?;
+  } else {
+    writer.write_event(Event::Start(start))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+  }
+
+  Ok(())
+}
+
+fn join<T: ToString>(data: &[T]) -> String {
+  data
+    .iter()
+    .map(ToString::to_string)
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+pub(super) fn from_reader<R: BufRead>(read: R) -> anyhow::Result<IndexMap<String, ContainerData>> {
+  let mut reader = Reader::from_reader(read);
+  let mut buf = Vec::new();
+
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Start(start) if start.name().as_ref() == ROOT.as_bytes() => {
+        return read_container(&mut reader);
+      }
+      Event::Empty(start) if start.name().as_ref() == ROOT.as_bytes() => {
+        return Ok(IndexMap::new());
+      }
+      Event::Decl(_) | Event::Comment(_) | Event::DocType(_) | Event::PI(_) => {}
+      Event::Text(text) if is_whitespace(&text) => {}
+      Event::Eof => return Err(anyhow!("Missing <{ROOT}> element")),
+      event => return Err(anyhow!("Expected <{ROOT}>, found {event:?}")),
+    }
+
+    buf.clear();
+  }
+}
+
+fn read_container<R: BufRead>(
+  reader: &mut Reader<R>,
+) -> anyhow::Result<IndexMap<String, ContainerData>> {
+  let mut children: IndexMap<String, ContainerData> = IndexMap::new();
+  let mut buf = Vec::new();
+
+  loop {
+    let (name, key, empty) = match reader.read_event_into(&mut buf)? {
+      Event::Start(start) => (element_name(&start)?, key_attribute(&start)?, false),
+      Event::Empty(start) => (element_name(&start)?, key_attribute(&start)?, true),
+      Event::End(_) => return Ok(children),
+      Event::Comment(_) | Event::PI(_) => continue,
+      Event::Text(text) if is_whitespace(&text) => continue,
+      Event::Eof => return Err(anyhow!("Unexpected end of file")),
+      event => return Err(anyhow!("Expected node element, found {event:?}")),
+    };
+    buf.clear();
+
+    let data = match name.as_str() {
+      CONTAINER if empty => Data::Container(IndexMap::new()),
+      CONTAINER => Data::Container(read_container(reader)?),
+      STRING_VEC if empty => Data::StringVec(Vec::new()),
+      STRING_VEC => Data::StringVec(read_items(reader)?),
+      name => {
+        let text = if empty {
+          String::new()
+        } else {
+          read_text(reader)?
+        };
+        parse_data(name, &text).map_err(|err| anyhow!("<{name} key=\"{key}\">: {err}"))?
+      }
+    };
+
+    if let Some(existing) = children.get_mut(&key) {
+      let mut list = match std::mem::replace(existing, ContainerData::Multiple(Vec::new())) {
+        ContainerData::Single(first) => vec![first],
+        ContainerData::Multiple(list) => list,
+      };
+      list.push(data);
+      *existing = ContainerData::Multiple(list);
+    } else {
+      children.insert(key, ContainerData::Single(data));
+    }
+  }
+}
+
+fn read_items<R: BufRead>(reader: &mut Reader<R>) -> anyhow::Result<Vec<String>> {
+  let mut items = Vec::new();
+  let mut buf = Vec::new();
+
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Start(start) if start.name().as_ref() == ITEM.as_bytes() => {
+        items.push(read_text(reader)?)
+      }
+      Event::Empty(start) if start.name().as_ref() == ITEM.as_bytes() => items.push(String::new()),
+      Event::End(_) => return Ok(items),
+      Event::Comment(_) | Event::PI(_) => {}
+      Event::Text(text) if is_whitespace(&text) => {}
+      Event::Eof => return Err(anyhow!("Unexpected end of file")),
+      event => return Err(anyhow!("Expected <{ITEM}>, found {event:?}")),
+    }
+
+    buf.clear();
+  }
+}
+
+fn read_text<R: BufRead>(reader: &mut Reader<R>) -> anyhow::Result<String> {
+  let mut text = String::new();
+  let mut buf = Vec::new();
+
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Text(content) => text.push_str(&content.unescape()?),
+      Event::CData(content) => text.push_str(std::str::from_utf8(&content)?),
+      Event::End(_) => return Ok(text),
+      Event::Comment(_) | Event::PI(_) => {}
+      Event::Eof => return Err(anyhow!("Unexpected end of file")),
+      event => return Err(anyhow!("Expected text, found {event:?}")),
+    }
+
+    buf.clear();
+  }
+}
+
+fn parse_data(name: &str, text: &str) -> anyhow::Result<Data> {
+  Ok(match name {
+    BINARY => Data::Binary(general_purpose::STANDARD_NO_PAD.decode(text.trim())?),
+    UUID => Data::Uuid(Uuid::parse_str(text.trim())?),
+    INT => Data::Int(text.trim().parse()?),
+    INT_VEC => Data::IntVec(
+      text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()?,
+    ),
+    FLOAT => Data::Float(text.trim().parse()?),
+    FLOAT_VEC => Data::FloatVec(
+      text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()?,
+    ),
+    STRING => Data::String(text.to_string()),
+    name => return Err(anyhow!("Unknown node type {name}")),
+  })
+}
+
+fn element_name(start: &BytesStart) -> anyhow::Result<String> {
+  Ok(std::str::from_utf8(start.name().as_ref())?.to_string())
+}
+
+fn key_attribute(start: &BytesStart) -> anyhow::Result<String> {
+  match start.try_get_attribute(KEY_ATTRIBUTE)? {
+    Some(attribute) => Ok(attribute.unescape_value()?.into_owned()),
+    None => Err(anyhow!(
+      "<{}> is missing the \"{KEY_ATTRIBUTE}\" attribute",
+      element_name(start)?
+    )),
+  }
+}
+
+fn is_whitespace(text: &BytesText) -> bool {
+  text.iter().all(u8::is_ascii_whitespace)
+}
@@ -1,13 +1,19 @@
-use std::fs::{create_dir_all, File};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
 
+use anyhow::anyhow;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 use indicatif::ProgressBar;
+use serde::Serialize;
 use soiboy::ComponentKind::Texture;
-use soiboy::{ComponentData, SoiSoup, Str};
+use soiboy::{repack, ComponentData, ComponentKind, PackagePaths, SoiSoup, Str, Toc};
 use x_flipper_360::{convert_to_dds, TextureHeader, TextureSize2D};
 
+use crate::oct::collect_files;
+
 #[derive(Parser)]
 pub(super) struct RREPackageModule {
   #[clap(subcommand)]
@@ -18,6 +24,10 @@ pub(super) struct RREPackageModule {
 pub(crate) enum Action {
   Unpack(UnpackAction),
   Ls(LsAction),
+  /// Writes the placement of every object as JSON
+  Scene(SceneAction),
+  /// Writes the package with the data of some components replaced
+  Replace(ReplaceAction),
 }
 
 #[derive(Parser)]
@@ -25,6 +35,10 @@ pub(crate) struct UnpackAction {
   soi: PathBuf,
   toc: PathBuf,
   str: PathBuf,
+
+  /// Writes the data of every component as is, instead of converting textures to dds
+  #[clap(long)]
+  raw: bool,
 }
 
 #[derive(Parser)]
@@ -33,11 +47,63 @@ pub(crate) struct LsAction {
   toc: PathBuf,
 }
 
+#[derive(Parser)]
+pub(crate) struct SceneAction {
+  soi: PathBuf,
+  toc: PathBuf,
+  output_file: PathBuf,
+}
+
+#[derive(Parser)]
+pub(crate) struct ReplaceAction {
+  soi: PathBuf,
+  toc: PathBuf,
+  str: PathBuf,
+  /// Folder with the data of the components to replace at their path, like unpack --raw writes it.
+  /// Textures are raw tiled data of the same size as the original, as their header in the soi
+  /// isn't rewritten
+  input_dir: PathBuf,
+  /// Folder to write the package to, under the same file names
+  output_dir: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Scene {
+  objects: Vec<SceneObject>,
+  /// Sections of the soi that weren't read, whose objects are missing from `objects`
+  unread_sections: Vec<UnreadSceneSection>,
+}
+
+#[derive(Serialize)]
+struct UnreadSceneSection {
+  section: &'static str,
+  reason: String,
+}
+
+#[derive(Serialize)]
+struct SceneObject {
+  section_id: u32,
+  component_id: u32,
+  kind: String,
+  name: String,
+  /// Path of the component in the toc, missing if the toc has no component with these ids
+  #[serde(skip_serializing_if = "Option::is_none")]
+  path: Option<String>,
+  position: [f32; 4],
+  look_vector: [f32; 4],
+  up_vector: [f32; 4],
+  zone: i32,
+  animated: bool,
+  flags: i32,
+}
+
 impl RREPackageModule {
   pub(super) fn execute(&self) -> anyhow::Result<()> {
     match &self.action {
       Action::Unpack(action) => action.execute(),
       Action::Ls(action) => action.execute(),
+      Action::Scene(action) => action.execute(),
+      Action::Replace(action) => action.execute(),
     }
   }
 }
@@ -52,18 +118,18 @@ impl UnpackAction {
     for (section_id, section) in soup.find_sections().iter().enumerate() {
       let section_data = str.read_section_data(section)?;
 
-      for component in section_data.uncached {
-        bar.inc(1);
-        extract_component(&soup, section_id as u32, &component)?;
-      }
-
-      for component in section_data.cached {
+      for component in section_data.uncached.iter().chain(&section_data.cached) {
         bar.inc(1);
-        extract_component(&soup, section_id as u32, &component)?;
+        if self.raw {
+          write_raw_component(component)?;
+        } else {
+          extract_component(&soup, section_id as u32, component)?;
+        }
       }
     }
 
     bar.finish_and_clear();
+    print_unread_sections(&soup);
 
     Ok(())
   }
@@ -83,34 +149,215 @@ impl LsAction {
     println!("-----------+-----------------------------------------------------------------------------|-------");
 
     for (section_id, _, component) in soup.find_components() {
-      if component.kind != Texture {
-        // TODO:
-        continue;
-      }
+      let id = component.id as u32;
+      let instance_id = component.instance_id as u32;
+
+      // textures show their format, other components only their kind
+      let format = match component.kind {
+        Texture => {
+          let metadata = soup
+            .find_texture_header(section_id, id, instance_id)
+            .unwrap()
+            .metadata();
+          format!("{:?}", metadata.format())
+        }
+        kind => format!("{kind:?}"),
+      };
 
-      let metadata = soup
-        .find_texture_header(
-          section_id,
-          component.id as u32,
-          component.instance_id as u32,
-        )
-        .unwrap()
-        .metadata();
-
-      println!(
-        "{:<10} | {:<75} | {:?}",
-        section_id,
-        component.path(),
-        metadata.format()
-      );
+      println!("{:<10} | {:<75} | {format}", section_id, component.path());
     }
 
     println!();
+    print_unread_sections(&soup);
+
+    Ok(())
+  }
+}
+
+impl SceneAction {
+  fn execute(&self) -> anyhow::Result<()> {
+    let soup = SoiSoup::<TextureHeader>::cook(self.toc.as_path(), self.soi.as_path())?;
+
+    let objects: Vec<_> = soup
+      .soi()
+      .model_infos()
+      .map(|(kind, model_info)| SceneObject {
+        section_id: model_info.section_id(),
+        component_id: model_info.component_id(),
+        kind: format!("{kind:?}"),
+        name: model_info.name(),
+        path: component_path(
+          &soup,
+          kind,
+          model_info.section_id(),
+          model_info.component_id(),
+        ),
+        position: model_info.position(),
+        look_vector: model_info.look_vector(),
+        up_vector: model_info.up_vector(),
+        zone: model_info.zone(),
+        animated: model_info.is_animated(),
+        flags: model_info.flags(),
+      })
+      .collect();
+
+    let unread_sections: Vec<_> = soup
+      .soi()
+      .unread_sections()
+      .into_iter()
+      .map(|unread| UnreadSceneSection {
+        section: unread.section,
+        reason: unread.reason,
+      })
+      .collect();
+
+    let scene = Scene {
+      objects,
+      unread_sections,
+    };
+    let output_file = BufWriter::new(File::create(&self.output_file)?);
+    serde_json::to_writer_pretty(output_file, &scene)?;
+
+    println!("Wrote {} objects", scene.objects.len());
+    print_unread_sections(&soup);
 
     Ok(())
   }
 }
 
+impl ReplaceAction {
+  fn execute(&self) -> anyhow::Result<()> {
+    let output_path = |input: &Path| -> anyhow::Result<PathBuf> {
+      let file_name = input
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", input.to_string_lossy()))?;
+      Ok(self.output_dir.join(file_name))
+    };
+    let (toc, soi, str) = (
+      output_path(&self.toc)?,
+      output_path(&self.soi)?,
+      output_path(&self.str)?,
+    );
+
+    // the package is read while it is written
+    for (input, output) in [(&self.toc, &toc), (&self.soi, &soi), (&self.str, &str)] {
+      if output.exists() && fs::canonicalize(input)? == fs::canonicalize(output)? {
+        return Err(anyhow!(
+          "{} would overwrite itself, choose another output folder",
+          input.to_string_lossy()
+        ));
+      }
+    }
+
+    // every file has to replace a component, so a mistyped path isn't silently left out
+    if !self.input_dir.is_dir() {
+      return Err(anyhow!(
+        "{} is not a folder",
+        self.input_dir.to_string_lossy()
+      ));
+    }
+    let toc_paths: HashSet<_> = Toc::read(&self.toc)?
+      .sections
+      .iter()
+      .flat_map(|section| {
+        section
+          .uncached_components
+          .iter()
+          .chain(&section.cached_components)
+      })
+      .map(|component| self.input_dir.join(component.path()))
+      .collect();
+    let mut files = Vec::new();
+    collect_files(&self.input_dir, &mut files)?;
+    let unmatched: Vec<_> = files
+      .iter()
+      .filter(|file| !toc_paths.contains(*file))
+      .map(|file| file.to_string_lossy())
+      .collect();
+    if !unmatched.is_empty() {
+      return Err(anyhow!(
+        "{} files match no component of the package, nothing was written:\n  {}",
+        unmatched.len(),
+        unmatched.join("\n  ")
+      ));
+    }
+
+    create_dir_all(&self.output_dir)?;
+
+    let input = PackagePaths {
+      toc: &self.toc,
+      soi: &self.soi,
+      str: &self.str,
+    };
+    let output = PackagePaths {
+      toc: &toc,
+      soi: &soi,
+      str: &str,
+    };
+    let replaced = repack(&input, &output, |_, component| {
+      let path = self.input_dir.join(&component.path);
+      match fs::read(&path) {
+        Ok(data) if component.kind == Texture && data.len() != component.data.len() => {
+          Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+              "{}: is {} bytes instead of the {} of the texture it replaces, textures have to \
+               keep their format and size",
+              path.to_string_lossy(),
+              data.len(),
+              component.data.len()
+            ),
+          ))
+        }
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(io::Error::new(
+          err.kind(),
+          format!("{}: {err}", path.to_string_lossy()),
+        )),
+      }
+    })?;
+
+    println!("Replaced {replaced} components");
+
+    Ok(())
+  }
+}
+
+/// Warns about the sections of the soi that weren't read, whose placements are missing.
+fn print_unread_sections(soup: &SoiSoup<TextureHeader>) {
+  for unread in soup.soi().unread_sections() {
+    println!(
+      "{} {} weren't read: {}",
+      "Warning:".yellow().bold(),
+      unread.section,
+      unread.reason
+    );
+  }
+}
+
+fn component_path(
+  soup: &SoiSoup<TextureHeader>,
+  kind: ComponentKind,
+  section_id: u32,
+  component_id: u32,
+) -> Option<String> {
+  let section = soup.find_sections().get(section_id as usize)?;
+  section
+    .uncached_components
+    .iter()
+    .chain(&section.cached_components)
+    .find(|component| component.id as u32 == component_id && component.kind == kind)
+    .map(|component| component.path())
+}
+
+fn write_raw_component(component: &ComponentData) -> anyhow::Result<()> {
+  let path = PathBuf::from(format!("data/out/{}", component.path));
+  create_dir_all(path.parent().unwrap())?;
+
+  Ok(fs::write(path, &component.data)?)
+}
+
 fn extract_component(
   soup: &SoiSoup<TextureHeader>,
   section_id: u32,
@@ -1,11 +1,17 @@
 use crate::bin_parser_writer::*;
 use binrw::{binrw, BinRead, BinWrite};
 
+/// Same as the version magic of [`BinDctHeader`], to tell the endian of a file.
+pub const DCT_VERSION: u32 = 0x2000;
+
 pub const HEADER_SIZE: usize = 8 * 4;
 pub const LINE_ENTRY_SIZE: usize = 3 * 4;
 pub const FOOTER_ENTRY_SIZE: usize = 6 * 4;
 pub const FOOTER_SUB_ENTRY_SIZE: usize = 2 * 4;
 
+/// Trails every footer entry, every known file uses the same values.
+pub const FOOTER_ENTRY_MAGIC: [u32; 4] = [0xFFFFFFDF, 11, 12, 0];
+
 #[derive(Debug, BinRead, BinWrite)]
 #[brw(magic = b"DICT")]
 #[allow(dead_code)]
@@ -47,14 +53,12 @@ pub struct BinDctFooterEntry {
   #[br(args { count: amount_sub_entries as usize })]
   pub sub_entries: Vec<BinDctFooterSubEntry>,
 
-  #[brw(magic = 0xFFFFFFDFu32)]
-  pub unknown0: (),
-  #[brw(magic = 11u32)]
-  pub unknown1: (),
-  #[brw(magic = 12u32)]
-  pub unknown2: (),
-  #[brw(magic = 0u32)]
-  pub unknown3: (),
+  #[br(assert(
+    magic == FOOTER_ENTRY_MAGIC,
+    "ends in {magic:08x?} instead of {FOOTER_ENTRY_MAGIC:08x?}, this dct uses a variant of the format that isn't supported"
+  ))]
+  #[bw(calc = FOOTER_ENTRY_MAGIC)]
+  pub magic: [u32; 4],
 }
 
 #[derive(Debug, BinRead, BinWrite)]
@@ -1,12 +1,14 @@
 use crate::bin_structure::{
-  BinDctFooterEntry, BinDctFooterSubEntry, BinDctHeader, BinDctLineEntry, FOOTER_ENTRY_SIZE,
-  FOOTER_SUB_ENTRY_SIZE, HEADER_SIZE, LINE_ENTRY_SIZE,
+  BinDctFooterEntry, BinDctFooterSubEntry, BinDctHeader, BinDctLineEntry, DCT_VERSION,
+  FOOTER_ENTRY_SIZE, FOOTER_SUB_ENTRY_SIZE, HEADER_SIZE, LINE_ENTRY_SIZE,
 };
 use crate::dct_map::DctLineError::{CapacityExceeded, KeyAlreadyExists, KeyDoesNotExist};
-use binrw::{BinRead, BinResult, BinWrite, NullString};
-use jenkins_hash::lookup2;
+use crate::encoding::{TextEncoding, TextError, TextLocation};
+use crate::hasher::{default_hasher, DctHasher};
+use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt, Endian, NullString};
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -25,54 +27,167 @@ struct LineEntry {
   pub text: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Error, Debug)]
+pub enum FooterError {
+  #[error("the text of footer entry {entry} contains a NUL byte, which would end it early")]
+  NulInText { entry: usize },
+  #[error(
+    "the text of sub entry {sub_entry} of footer entry {entry} contains a NUL byte, which would end it early"
+  )]
+  NulInSubEntryText { entry: usize, sub_entry: usize },
+}
+
+/// An entry of the footer at the end of a dct, a string with a list of strings that are each
+/// paired with a number.
+///
+/// What the game reads them for isn't known, so they are kept as they were read, and
+/// [`FooterEntry::validate`] only checks what the format can store.
+#[derive(Clone, Debug, PartialEq)]
 pub struct FooterEntry {
   pub text: String,
   pub sub_entries: Vec<FooterSubEntry>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FooterSubEntry {
   pub text: String,
   pub to_map_to: u32,
 }
 
+impl FooterEntry {
+  /// Checks that the entry can be written, `entry` is its index for the error.
+  pub fn validate(&self, entry: usize) -> Result<(), FooterError> {
+    if self.text.contains('\0') {
+      return Err(FooterError::NulInText { entry });
+    }
+
+    match self
+      .sub_entries
+      .iter()
+      .position(|sub_entry| sub_entry.text.contains('\0'))
+    {
+      Some(sub_entry) => Err(FooterError::NulInSubEntryText { entry, sub_entry }),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Load factor [`DctMap`] grows at by default, which leaves room for short probe chains.
+pub const DEFAULT_MAX_LOAD_FACTOR: f32 = 0.7;
+
+const EMPTY_LINE_ENTRY: LineEntry = LineEntry {
+  line_id: 0,
+  text: None,
+};
+
+/// Hash table of lines by the hash of their key, resolving collisions by linear probing.
 #[derive(Clone)]
 pub struct DctMap {
+  endian: Endian,
+  hasher: Arc<dyn DctHasher>,
   initial_hash_value: u32,
   line_entries: Vec<LineEntry>,
+  line_count: usize,
+  max_load_factor: Option<f32>,
+  encoding: TextEncoding,
   pub footer_entries: Vec<FooterEntry>,
 }
 
+/// Number of slots that holds `line_count` lines at `max_load_factor`, which is clamped like
+/// [`DctMap::set_max_load_factor`] does.
+pub fn capacity_for(line_count: usize, max_load_factor: f32) -> u32 {
+  (line_count as f32 / max_load_factor.clamp(f32::EPSILON, 1.0)).ceil() as u32
+}
+
 impl DctMap {
+  /// Creates an empty map with `capacity` slots, which grows once it is filled beyond
+  /// [`DEFAULT_MAX_LOAD_FACTOR`].
   pub fn new(initial_hash_value: u32, capacity: u32, footer_entries: Vec<FooterEntry>) -> Self {
+    Self::with_hasher(
+      default_hasher(),
+      initial_hash_value,
+      capacity,
+      footer_entries,
+    )
+  }
+
+  /// Creates an empty map like [`DctMap::new`] that hashes its keys with `hasher`.
+  pub fn with_hasher(
+    hasher: Arc<dyn DctHasher>,
+    initial_hash_value: u32,
+    capacity: u32,
+    footer_entries: Vec<FooterEntry>,
+  ) -> Self {
     Self {
+      endian: Endian::Little,
+      hasher,
       initial_hash_value,
-      line_entries: vec![
-        LineEntry {
-          line_id: 0,
-          text: None,
-        };
-        capacity as usize
-      ],
+      line_entries: vec![EMPTY_LINE_ENTRY; capacity as usize],
+      line_count: 0,
+      max_load_factor: Some(DEFAULT_MAX_LOAD_FACTOR),
+      encoding: TextEncoding::Utf8,
       footer_entries,
     }
   }
 
+  /// Reads a dct in the endian its header is written in.
   pub fn from_reader<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
-    let header = BinDctHeader::read_le(reader)?;
+    let endian = Self::detect_endian(reader)?;
+    Self::from_reader_with_endian(reader, endian)
+  }
+
+  /// Tells the endian of a dct by its version, which is the same for PC and console files.
+  pub fn detect_endian<R: Read + Seek>(reader: &mut R) -> BinResult<Endian> {
+    let start = reader.stream_position()?;
+
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let version = [header[4], header[5], header[6], header[7]];
+    if u32::from_le_bytes(version) == DCT_VERSION {
+      Ok(Endian::Little)
+    } else if u32::from_be_bytes(version) == DCT_VERSION {
+      Ok(Endian::Big)
+    } else {
+      Err(binrw::Error::AssertFail {
+        pos: start + 4,
+        message: format!("unknown dct version {version:02x?}"),
+      })
+    }
+  }
+
+  pub fn from_reader_with_endian<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+  ) -> BinResult<Self> {
+    Self::from_reader_with_options(reader, Some(endian), TextEncoding::Utf8)
+  }
+
+  /// Reads a dct whose strings are stored in `encoding`, in `endian` or the one its header is
+  /// written in. Strings that aren't valid in `encoding` fail with a [`TextError`].
+  pub fn from_reader_with_options<R: Read + Seek>(
+    reader: &mut R,
+    endian: Option<Endian>,
+    encoding: TextEncoding,
+  ) -> BinResult<Self> {
+    let endian = match endian {
+      Some(endian) => endian,
+      None => Self::detect_endian(reader)?,
+    };
+    let header: BinDctHeader = reader.read_type(endian)?;
 
     let mut line_entries = Vec::with_capacity(header.line_count as usize);
     for _ in 0..header.line_count as usize {
-      let bin_line_entry = BinDctLineEntry::read_le(reader)?;
+      let bin_line_entry: BinDctLineEntry = reader.read_type(endian)?;
 
       let text = match bin_line_entry.text_offset {
         None => None,
         Some(text_offset) => {
           let cur_pos = reader.stream_position()?;
 
-          reader.seek(SeekFrom::Start(text_offset))?;
-          let text = NullString::read_le(reader)?.to_string();
+          let location = TextLocation::Line(bin_line_entry.line_id);
+          let text = read_text(reader, text_offset, &encoding, location)?;
 
           reader.seek(SeekFrom::Start(cur_pos))?;
           Some(text)
@@ -87,20 +202,29 @@ impl DctMap {
     }
 
     let mut footer_entries = Vec::<FooterEntry>::with_capacity(header.footer_count as usize);
-    for _ in 0..header.footer_count {
-      let bin_footer_entry = BinDctFooterEntry::read_le(reader)?;
+    for entry in 0..header.footer_count {
+      let bin_footer_entry: BinDctFooterEntry =
+        reader.read_type(endian).map_err(|err| match err {
+          binrw::Error::AssertFail { pos, message } => binrw::Error::AssertFail {
+            pos,
+            message: format!("footer entry {entry}: {message}"),
+          },
+          err => err,
+        })?;
 
       let cur_pos = reader.stream_position()?;
-      reader.seek(SeekFrom::Start(bin_footer_entry.text_offset))?;
-      let text = NullString::read_le(reader)?.to_string();
+      let entry = entry as usize;
+      let location = TextLocation::FooterEntry(entry);
+      let text = read_text(reader, bin_footer_entry.text_offset, &encoding, location)?;
 
       let sub_entries: BinResult<Vec<FooterSubEntry>> = bin_footer_entry
         .sub_entries
         .iter()
-        .map(|sub_entry| {
-          reader.seek(SeekFrom::Start(sub_entry.text_offset))?;
+        .enumerate()
+        .map(|(sub_entry_index, sub_entry)| {
+          let location = TextLocation::FooterSubEntry(entry, sub_entry_index);
           Ok(FooterSubEntry {
-            text: NullString::read_le(reader)?.to_string(),
+            text: read_text(reader, sub_entry.text_offset, &encoding, location)?,
             to_map_to: sub_entry.to_map_to,
           })
         })
@@ -115,14 +239,34 @@ impl DctMap {
       footer_entries.push(footer_entry);
     }
 
+    let line_count = line_entries
+      .iter()
+      .filter(|entry| entry.line_id != 0)
+      .count();
+
     Ok(Self {
+      endian,
+      hasher: default_hasher(),
       initial_hash_value: header.initial_hash_value,
       line_entries,
+      line_count,
+      max_load_factor: Some(DEFAULT_MAX_LOAD_FACTOR),
+      encoding,
       footer_entries,
     })
   }
 
+  /// Writes the dct in the endian it was read in, see [`DctMap::set_endian`].
   pub fn to_writer<W: Write + Seek>(&self, writer: &mut W) -> BinResult<()> {
+    let endian = self.endian;
+
+    if let Err(err) = self.validate_footer() {
+      return Err(binrw::Error::Custom {
+        pos: writer.stream_position()?,
+        err: Box::new(err),
+      });
+    }
+
     let line_count = self.line_entries.len();
     let footer_count = self.footer_entries.len();
     let footer_sub_entry_count = self
@@ -144,33 +288,38 @@ impl DctMap {
       footer_offset: (HEADER_SIZE + line_chunk_size - 1) as u32,
       footer_count: footer_count as u32,
     };
-    header.write_le(writer)?;
+    writer.write_type(&header, endian)?;
 
     let mut text_offset_map = HashMap::<String, u64>::new();
+    let mut strings_to_write = Vec::new();
     let mut cur_eof = (HEADER_SIZE + line_chunk_size + footer_chunk_size) as u64;
 
-    let mut get_footer_offset_from_string = |text: &str| -> u64 {
-      return match text_offset_map.get(text) {
-        None => {
-          let text_offset = cur_eof;
+    let mut get_footer_offset_from_string = |text: &str, location| -> BinResult<u64> {
+      if let Some(text_offset) = text_offset_map.get(text) {
+        return Ok(*text_offset);
+      }
 
-          text_offset_map.insert(text.to_string(), text_offset);
-          cur_eof += (text.len() + 1) as u64; // include null-byte
+      let text_offset = cur_eof;
+      let bytes = self
+        .encoding
+        .encode(text, location)
+        .map_err(|err| text_error(text_offset, err))?;
 
-          text_offset
-        }
-        Some(text_offset) => *text_offset,
-      };
+      text_offset_map.insert(text.to_string(), text_offset);
+      cur_eof += (bytes.len() + 1) as u64; // include null-byte
+      strings_to_write.push(bytes);
+
+      Ok(text_offset)
     };
 
     for line_entry in &self.line_entries {
       if line_entry.line_id == 0 {
-        BinDctLineEntry {
+        let bin_line_entry = BinDctLineEntry {
           line_id: 0,
           text_offset: None,
           unknown: (),
-        }
-        .write_le(writer)?;
+        };
+        writer.write_type(&bin_line_entry, endian)?;
 
         continue;
       }
@@ -179,7 +328,8 @@ impl DctMap {
         .text
         .as_ref()
         .expect("line_id is not 0 but text is none");
-      let text_offset = get_footer_offset_from_string(text);
+      let text_offset =
+        get_footer_offset_from_string(text, TextLocation::Line(line_entry.line_id))?;
 
       let bin_line_entry = BinDctLineEntry {
         line_id: line_entry.line_id,
@@ -187,42 +337,37 @@ impl DctMap {
         unknown: (),
       };
 
-      bin_line_entry.write_le(writer)?;
+      writer.write_type(&bin_line_entry, endian)?;
     }
 
-    for footer_entry in &self.footer_entries {
-      let text_offset = get_footer_offset_from_string(&footer_entry.text);
+    for (entry, footer_entry) in self.footer_entries.iter().enumerate() {
+      let text_offset =
+        get_footer_offset_from_string(&footer_entry.text, TextLocation::FooterEntry(entry))?;
 
-      let bin_sub_entries: Vec<_> = footer_entry
+      let bin_sub_entries = footer_entry
         .sub_entries
         .iter()
-        .map(|sub_entry| {
-          let text_offset = get_footer_offset_from_string(&sub_entry.text);
-          BinDctFooterSubEntry {
-            text_offset,
+        .enumerate()
+        .map(|(sub_entry_index, sub_entry)| {
+          let location = TextLocation::FooterSubEntry(entry, sub_entry_index);
+          Ok(BinDctFooterSubEntry {
+            text_offset: get_footer_offset_from_string(&sub_entry.text, location)?,
             to_map_to: sub_entry.to_map_to,
-          }
+          })
         })
-        .collect();
+        .collect::<BinResult<Vec<_>>>()?;
 
       let bin_footer_entry = BinDctFooterEntry {
         text_offset,
         sub_entries: bin_sub_entries,
-        unknown0: (),
-        unknown1: (),
-        unknown2: (),
-        unknown3: (),
       };
 
-      bin_footer_entry.write_le(writer)?;
+      writer.write_type(&bin_footer_entry, endian)?;
     }
 
-    // sort strings by offset to write them in the correct order
-    let mut string_to_write: Vec<_> = text_offset_map.iter().collect();
-    string_to_write.sort_by(|(_, a), (_, b)| a.cmp(b));
-
-    for (text, _) in string_to_write {
-      NullString::from(text.as_str()).write_le(writer)?;
+    // strings were encoded in the order of their offsets
+    for bytes in strings_to_write {
+      NullString(bytes).write_le(writer)?;
     }
 
     Ok(())
@@ -230,6 +375,9 @@ impl DctMap {
 
   fn mod_entry_lookup(&self, hashed_key: u32) -> Option<usize> {
     let dct_capacity = self.line_entries.len();
+    if dct_capacity == 0 {
+      return None;
+    }
 
     let mut position_guess = hashed_key as usize % dct_capacity;
     for _ in 0..dct_capacity {
@@ -245,41 +393,198 @@ impl DctMap {
     None
   }
 
+  /// The line id `key` is stored under.
+  pub fn hash_key(&self, key: &str) -> u32 {
+    self.hasher.hash(key.as_bytes(), self.initial_hash_value)
+  }
+
   pub fn get_line_entry(&self, key: &str) -> Result<&str, DctLineError> {
-    let hashed_key = lookup2(key.as_bytes(), self.initial_hash_value);
+    self
+      .get_line_entry_by_hash(self.hash_key(key))
+      .map_err(|_| KeyDoesNotExist(key.to_string()))
+  }
+
+  pub fn get_line_entry_by_hash(&self, hashed_key: u32) -> Result<&str, DctLineError> {
+    match self.mod_entry_lookup(hashed_key) {
+      Some(entry_index) if self.line_entries[entry_index].line_id == hashed_key => {
+        Ok(self.line_entries[entry_index].text.as_ref().unwrap())
+      }
+      _ => Err(KeyDoesNotExist(format!("{hashed_key:#010x}"))),
+    }
+  }
+
+  pub fn add_line_entry(&mut self, key: &str, text: &str) -> Result<(), DctLineError> {
+    self
+      .add_line_entry_by_hash(self.hash_key(key), text)
+      .map_err(|err| match err {
+        KeyAlreadyExists(_) => KeyAlreadyExists(key.to_string()),
+        err => err,
+      })
+  }
 
-    return match self.mod_entry_lookup(hashed_key) {
-      None => Err(KeyDoesNotExist(key.to_string())),
-      Some(entry_index) => {
-        let entry = &self.line_entries[entry_index];
+  /// Adds a line whose key is unknown, under the line id the key hashes to.
+  pub fn add_line_entry_by_hash(
+    &mut self,
+    hashed_key: u32,
+    text: &str,
+  ) -> Result<(), DctLineError> {
+    if let Some(entry_index) = self.mod_entry_lookup(hashed_key) {
+      if self.line_entries[entry_index].line_id == hashed_key {
+        return Err(KeyAlreadyExists(format!("{hashed_key:#010x}")));
+      }
+    }
 
-        if entry.line_id == 0 {
-          return Err(KeyDoesNotExist(key.to_string()));
-        }
+    self.reserve_one()?;
 
-        Ok(entry.text.as_ref().unwrap())
+    let entry_index = self.mod_entry_lookup(hashed_key).ok_or(CapacityExceeded)?;
+    self.line_entries[entry_index] = LineEntry {
+      line_id: hashed_key,
+      text: Some(text.to_string()),
+    };
+    self.line_count += 1;
+
+    Ok(())
+  }
+
+  /// Adds the line, or replaces its text if it exists, returning the replaced text.
+  pub fn set_line_entry(&mut self, key: &str, text: &str) -> Result<Option<String>, DctLineError> {
+    self.set_line_entry_by_hash(self.hash_key(key), text)
+  }
+
+  pub fn set_line_entry_by_hash(
+    &mut self,
+    hashed_key: u32,
+    text: &str,
+  ) -> Result<Option<String>, DctLineError> {
+    if let Some(entry_index) = self.mod_entry_lookup(hashed_key) {
+      let entry = &mut self.line_entries[entry_index];
+      if entry.line_id == hashed_key {
+        return Ok(entry.text.replace(text.to_string()));
       }
+    }
+
+    self.add_line_entry_by_hash(hashed_key, text)?;
+    Ok(None)
+  }
+
+  /// Removes the line, returning its text.
+  pub fn remove_line_entry(&mut self, key: &str) -> Result<String, DctLineError> {
+    self
+      .remove_line_entry_by_hash(self.hash_key(key))
+      .map_err(|err| match err {
+        KeyDoesNotExist(_) => KeyDoesNotExist(key.to_string()),
+        err => err,
+      })
+  }
+
+  /// Removes the line, moving the lines probed past it back so every line stays reachable.
+  pub fn remove_line_entry_by_hash(&mut self, hashed_key: u32) -> Result<String, DctLineError> {
+    let mut hole = match self.mod_entry_lookup(hashed_key) {
+      Some(entry_index) if self.line_entries[entry_index].line_id == hashed_key => entry_index,
+      _ => return Err(KeyDoesNotExist(format!("{hashed_key:#010x}"))),
     };
+
+    let removed = std::mem::replace(&mut self.line_entries[hole], EMPTY_LINE_ENTRY);
+    self.line_count -= 1;
+
+    let dct_capacity = self.line_entries.len();
+    let mut position = hole;
+    for _ in 1..dct_capacity {
+      position = (position + 1) % dct_capacity;
+      let line_id = self.line_entries[position].line_id;
+      if line_id == 0 {
+        break;
+      }
+
+      // a line can move back into the hole if the hole lies between its home slot and its slot
+      let home = line_id as usize % dct_capacity;
+      let distance_to_hole = (hole + dct_capacity - home) % dct_capacity;
+      let distance_to_slot = (position + dct_capacity - home) % dct_capacity;
+      if distance_to_hole < distance_to_slot {
+        self.line_entries.swap(hole, position);
+        hole = position;
+      }
+    }
+
+    Ok(removed.text.unwrap())
   }
 
-  pub fn add_line_entry(&mut self, key: &str, text: &str) -> Result<(), DctLineError> {
-    let hashed_key = lookup2(key.as_bytes(), self.initial_hash_value);
+  /// Limits how full the map gets before [`DctMap::add_line_entry`] grows it. `None` keeps the
+  /// capacity, so adding fails with [`DctLineError::CapacityExceeded`] once every slot is used.
+  pub fn set_max_load_factor(&mut self, max_load_factor: Option<f32>) {
+    self.max_load_factor = max_load_factor.map(|factor| factor.clamp(f32::EPSILON, 1.0));
+  }
 
-    match self.mod_entry_lookup(hashed_key) {
-      None => Err(CapacityExceeded),
-      Some(entry_index) => {
-        let entry = &mut self.line_entries[entry_index];
+  pub fn get_max_load_factor(&self) -> Option<f32> {
+    self.max_load_factor
+  }
 
-        if entry.line_id == hashed_key {
-          return Err(KeyAlreadyExists(key.to_string()));
-        }
+  /// Moves every line into a table of `capacity` slots, in the order of their current slots.
+  pub fn rehash(&mut self, capacity: u32) -> Result<(), DctLineError> {
+    if (capacity as usize) < self.line_count {
+      return Err(CapacityExceeded);
+    }
+
+    let line_entries = std::mem::replace(
+      &mut self.line_entries,
+      vec![EMPTY_LINE_ENTRY; capacity as usize],
+    );
+    for line_entry in line_entries {
+      if line_entry.line_id != 0 {
+        let entry_index = self.mod_entry_lookup(line_entry.line_id).unwrap();
+        self.line_entries[entry_index] = line_entry;
+      }
+    }
 
-        entry.line_id = hashed_key;
-        entry.text = Some(text.to_string());
+    Ok(())
+  }
+
+  /// Makes room for one more line, growing the map if it would exceed its load factor.
+  fn reserve_one(&mut self) -> Result<(), DctLineError> {
+    let line_count = self.line_count + 1;
+    let dct_capacity = self.line_entries.len();
 
-        Ok(())
+    let Some(max_load_factor) = self.max_load_factor else {
+      if line_count > dct_capacity {
+        return Err(CapacityExceeded);
       }
+      return Ok(());
+    };
+
+    if line_count as f32 <= dct_capacity as f32 * max_load_factor {
+      return Ok(());
     }
+
+    let capacity = capacity_for(line_count, max_load_factor).max(dct_capacity as u32 * 2);
+    self.rehash(capacity)
+  }
+
+  pub fn get_endian(&self) -> Endian {
+    self.endian
+  }
+
+  pub fn set_endian(&mut self, endian: Endian) {
+    self.endian = endian;
+  }
+
+  pub fn get_hasher(&self) -> &Arc<dyn DctHasher> {
+    &self.hasher
+  }
+
+  /// Sets the hash of the keys, which files don't record, so read maps start with lookup2.
+  ///
+  /// Lines stay in their slots, as they are stored by their line id.
+  pub fn set_hasher(&mut self, hasher: Arc<dyn DctHasher>) {
+    self.hasher = hasher;
+  }
+
+  pub fn get_encoding(&self) -> &TextEncoding {
+    &self.encoding
+  }
+
+  /// Sets the encoding [`DctMap::to_writer`] stores strings in.
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.encoding = encoding;
   }
 
   pub fn get_initial_hash_value(&self) -> u32 {
@@ -291,53 +596,152 @@ impl DctMap {
   }
 
   pub fn get_current_capacity(&self) -> u32 {
-    self
+    self.line_count as u32
+  }
+
+  pub fn validate_footer(&self) -> Result<(), FooterError> {
+    for (entry, footer_entry) in self.footer_entries.iter().enumerate() {
+      footer_entry.validate(entry)?;
+    }
+    Ok(())
+  }
+
+  /// Line ids in an order that puts every line back into its current slot when they are added to
+  /// an empty map of the same capacity.
+  ///
+  /// Each run of used slots is listed from its start, so every line finds the slots before it in
+  /// the run taken when it is added.
+  pub fn probe_order(&self) -> Vec<u32> {
+    let dct_capacity = self.line_entries.len();
+    let start = self
       .line_entries
       .iter()
-      .filter(|entry| entry.line_id != 0)
-      .count() as u32
+      .position(|entry| entry.line_id == 0)
+      .map_or(0, |empty| empty + 1);
+
+    (0..dct_capacity)
+      .map(|offset| self.line_entries[(start + offset) % dct_capacity].line_id)
+      .filter(|line_id| *line_id != 0)
+      .collect()
   }
 
   pub fn iter_line_entries(&self) -> DctLineEntryIterator {
     DctLineEntryIterator {
+      slots: self.iter_slots(),
+    }
+  }
+
+  /// Iterates every slot of the table, including the empty ones.
+  pub fn iter_slots(&self) -> DctSlotIterator<'_> {
+    DctSlotIterator {
       dct_map: self,
       index: 0,
     }
   }
 }
 
+/// Wraps a text that can't be decoded or encoded, at `pos` of the file, into a binrw error.
+fn text_error(pos: u64, err: TextError) -> binrw::Error {
+  binrw::Error::Custom {
+    pos,
+    err: Box::new(err),
+  }
+}
+
+fn read_text<R: Read + Seek>(
+  reader: &mut R,
+  text_offset: u64,
+  encoding: &TextEncoding,
+  location: TextLocation,
+) -> BinResult<String> {
+  reader.seek(SeekFrom::Start(text_offset))?;
+  let bytes = NullString::read_le(reader)?.0;
+
+  encoding
+    .decode(&bytes, location)
+    .map_err(|err| text_error(text_offset, err))
+}
+
+/// Iterates the line id and text of every line, in slot order.
 pub struct DctLineEntryIterator<'a> {
-  dct_map: &'a DctMap,
-  index: usize,
+  slots: DctSlotIterator<'a>,
 }
 
 impl<'a> Iterator for DctLineEntryIterator<'a> {
   type Item = (u32, &'a str);
 
   fn next(&mut self) -> Option<Self::Item> {
-    let capacity_map = self.dct_map.line_entries.len();
-    if capacity_map == 0 {
-      return None;
-    }
+    self
+      .slots
+      .find_map(|slot| slot.line)
+      .map(|line| (line.line_id, line.text))
+  }
+}
 
-    loop {
-      if self.index == capacity_map - 1 {
-        return None;
-      }
+/// A slot of the table, see [`DctMap::iter_slots`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DctSlot<'a> {
+  pub index: usize,
+  /// `None` if the slot is empty
+  pub line: Option<DctSlotLine<'a>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DctSlotLine<'a> {
+  pub line_id: u32,
+  pub text: &'a str,
+  /// Number of slots the line was moved past the one its line id maps to
+  pub probe_length: usize,
+}
+
+pub struct DctSlotIterator<'a> {
+  dct_map: &'a DctMap,
+  index: usize,
+}
+
+impl<'a> Iterator for DctSlotIterator<'a> {
+  type Item = DctSlot<'a>;
 
-      let entry = &self.dct_map.line_entries[self.index];
-      self.index += 1;
-      if entry.line_id != 0 {
-        return Some((entry.line_id, &entry.text.as_ref().unwrap()));
+  fn next(&mut self) -> Option<Self::Item> {
+    let line_entries = &self.dct_map.line_entries;
+    let dct_capacity = line_entries.len();
+
+    let index = self.index;
+    let entry = line_entries.get(index)?;
+    self.index += 1;
+
+    let line = (entry.line_id != 0).then(|| {
+      let home = entry.line_id as usize % dct_capacity;
+      DctSlotLine {
+        line_id: entry.line_id,
+        text: entry.text.as_ref().unwrap(),
+        probe_length: (index + dct_capacity - home) % dct_capacity,
       }
-    }
+    });
+
+    Some(DctSlot { index, line })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.dct_map.line_entries.len() - self.index;
+    (remaining, Some(remaining))
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::dct_map::DctMap;
+  use crate::bin_structure::{FOOTER_SUB_ENTRY_SIZE, HEADER_SIZE, LINE_ENTRY_SIZE};
+  use crate::dct_map::{
+    capacity_for, DctLineError, DctMap, DctSlot, DctSlotLine, FooterEntry, FooterError,
+    FooterSubEntry, DEFAULT_MAX_LOAD_FACTOR,
+  };
+  use crate::encoding::TextEncoding;
+  use crate::hasher::tests::Fnv1a;
+  use crate::hasher::DctHasher;
+  use binrw::Endian;
   use jenkins_hash::lookup2;
+  use std::io::Cursor;
+  use std::sync::Arc;
 
   #[test]
   fn test_add_and_get() {
@@ -370,4 +774,233 @@ mod tests {
     assert!(iter_res.contains(&(lookup2("key1".as_bytes(), INITIAL_HASH_VALUE), "test1")));
     assert!(iter_res.contains(&(lookup2("key2".as_bytes(), INITIAL_HASH_VALUE), "test2")));
   }
+
+  #[test]
+  fn test_iterate_last_slot() {
+    let mut dct_map = DctMap::new(0, 4, vec![]);
+    dct_map.set_max_load_factor(None);
+
+    // 3 takes the last slot, 7 wraps around to the first one
+    dct_map.add_line_entry_by_hash(3, "last").unwrap();
+    dct_map.add_line_entry_by_hash(7, "wrapped").unwrap();
+
+    let lines: Vec<_> = dct_map.iter_line_entries().collect();
+    assert_eq!(lines, [(7, "wrapped"), (3, "last")]);
+
+    let slots: Vec<_> = dct_map.iter_slots().collect();
+    assert_eq!(slots.len(), 4);
+    assert_eq!(
+      slots[1],
+      DctSlot {
+        index: 1,
+        line: None
+      }
+    );
+    assert_eq!(
+      slots[0].line,
+      Some(DctSlotLine {
+        line_id: 7,
+        text: "wrapped",
+        probe_length: 1
+      })
+    );
+    assert_eq!(slots[3].line.unwrap().probe_length, 0);
+  }
+
+  #[test]
+  fn test_grow() {
+    let mut dct_map = DctMap::new(0x1FEDBEEF, 2, vec![]);
+
+    for i in 0..100 {
+      dct_map.add_line_entry(&format!("key{i}"), "text").unwrap();
+    }
+
+    assert_eq!(dct_map.get_current_capacity(), 100);
+    assert!(dct_map.get_max_capacity() as f32 * DEFAULT_MAX_LOAD_FACTOR >= 100.0);
+    assert!((0..100).all(|i| dct_map.get_line_entry(&format!("key{i}")).is_ok()));
+
+    assert_eq!(capacity_for(100, 0.5), 200);
+    assert_eq!(capacity_for(100, 2.0), 100);
+
+    let mut fixed = DctMap::new(0x1FEDBEEF, 1, vec![]);
+    fixed.set_max_load_factor(None);
+    fixed.add_line_entry("key", "text").unwrap();
+    assert!(matches!(
+      fixed.add_line_entry("other", "text"),
+      Err(DctLineError::CapacityExceeded)
+    ));
+  }
+
+  #[test]
+  fn test_remove_keeps_probe_chains() {
+    let mut dct_map = DctMap::new(0, 8, vec![]);
+    dct_map.set_max_load_factor(None);
+
+    // 7, 15 and 23 all hash to slot 7 and wrap around, 1 is pushed to slot 2 by them
+    for line_id in [7, 15, 23, 1] {
+      dct_map
+        .add_line_entry_by_hash(line_id, &line_id.to_string())
+        .unwrap();
+    }
+
+    assert_eq!(dct_map.remove_line_entry_by_hash(15).unwrap(), "15");
+    assert!(dct_map.remove_line_entry_by_hash(15).is_err());
+
+    for line_id in [7, 23, 1] {
+      assert_eq!(
+        dct_map.get_line_entry_by_hash(line_id).unwrap(),
+        line_id.to_string()
+      );
+    }
+    assert_eq!(dct_map.get_current_capacity(), 3);
+
+    let mut dct_map = DctMap::new(0x1FEDBEEF, 4, vec![]);
+    dct_map.add_line_entry("key", "text").unwrap();
+    assert_eq!(dct_map.remove_line_entry("key").unwrap(), "text");
+    assert!(matches!(
+      dct_map.remove_line_entry("key"),
+      Err(DctLineError::KeyDoesNotExist(key)) if key == "key"
+    ));
+  }
+
+  #[test]
+  fn test_set_line_entry() {
+    let mut dct_map = DctMap::new(0x1FEDBEEF, 0, vec![]);
+
+    assert_eq!(dct_map.set_line_entry("key", "old").unwrap(), None);
+    assert_eq!(
+      dct_map.set_line_entry("key", "new").unwrap(),
+      Some("old".to_string())
+    );
+    assert_eq!(dct_map.get_line_entry("key").unwrap(), "new");
+    assert_eq!(dct_map.get_current_capacity(), 1);
+  }
+
+  #[test]
+  fn test_probe_order() {
+    let mut dct_map = DctMap::new(0, 8, vec![]);
+    dct_map.set_max_load_factor(None);
+
+    // 15 wraps around to slot 0, which pushes 8 to slot 1
+    for (line_id, text) in [(7, "a"), (15, "b"), (8, "c"), (3, "d")] {
+      dct_map.add_line_entry_by_hash(line_id, text).unwrap();
+    }
+
+    let order = dct_map.probe_order();
+    assert_eq!(order, [3, 7, 15, 8]);
+
+    let mut repacked = DctMap::new(0, 8, vec![]);
+    for line_id in order {
+      let text = dct_map.get_line_entry_by_hash(line_id).unwrap();
+      repacked.add_line_entry_by_hash(line_id, text).unwrap();
+    }
+
+    let mut original = Cursor::new(Vec::new());
+    let mut copy = Cursor::new(Vec::new());
+    dct_map.to_writer(&mut original).unwrap();
+    repacked.to_writer(&mut copy).unwrap();
+    assert_eq!(original.into_inner(), copy.into_inner());
+  }
+
+  #[test]
+  fn test_footer() {
+    let footer_entries = vec![FooterEntry {
+      text: "font".to_string(),
+      sub_entries: vec![FooterSubEntry {
+        text: "\u{e4}".to_string(),
+        to_map_to: 0xe4,
+      }],
+    }];
+
+    let mut dct_map = DctMap::new(0, 2, footer_entries.clone());
+    let mut buf = Cursor::new(Vec::new());
+    dct_map.to_writer(&mut buf).unwrap();
+
+    buf.set_position(0);
+    let read = DctMap::from_reader(&mut buf).unwrap();
+    assert_eq!(read.footer_entries, footer_entries);
+
+    // the third magic value of the footer entry, after its text offset, count and sub entry
+    let mut changed = buf.into_inner();
+    let position = HEADER_SIZE + 2 * LINE_ENTRY_SIZE + 2 * 4 + FOOTER_SUB_ENTRY_SIZE + 2 * 4;
+    changed[position] = 13;
+    let err = DctMap::from_reader(&mut Cursor::new(changed))
+      .err()
+      .unwrap()
+      .to_string();
+    assert!(err.contains("footer entry 0"), "{err}");
+
+    dct_map.footer_entries[0].sub_entries[0].text = "a\0b".to_string();
+    assert!(matches!(
+      dct_map.validate_footer(),
+      Err(FooterError::NulInSubEntryText {
+        entry: 0,
+        sub_entry: 0
+      })
+    ));
+    assert!(dct_map.to_writer(&mut Cursor::new(Vec::new())).is_err());
+  }
+
+  #[test]
+  fn test_endian_round_trip() {
+    for endian in [Endian::Little, Endian::Big] {
+      let mut dct_map = DctMap::new(0x1FEDBEEF, 4, vec![]);
+      dct_map.set_endian(endian);
+      dct_map.add_line_entry("key", "text").unwrap();
+
+      let mut buf = Cursor::new(Vec::new());
+      dct_map.to_writer(&mut buf).unwrap();
+      buf.set_position(0);
+
+      let read = DctMap::from_reader(&mut buf).unwrap();
+      assert_eq!(read.get_endian(), endian);
+      assert_eq!(read.get_initial_hash_value(), 0x1FEDBEEF);
+      assert_eq!(read.get_line_entry("key").unwrap(), "text");
+    }
+  }
+
+  #[test]
+  fn test_encoding_round_trip() {
+    let windows = TextEncoding::from_label("windows-1252").unwrap();
+    let mut dct_map = DctMap::new(0, 4, vec![]);
+    dct_map.set_encoding(windows.clone());
+    dct_map.add_line_entry("key", "Café").unwrap();
+
+    let mut buf = Cursor::new(Vec::new());
+    dct_map.to_writer(&mut buf).unwrap();
+    let bytes = buf.into_inner();
+    assert!(bytes.ends_with(b"Caf\xe9\0"));
+
+    let read =
+      DctMap::from_reader_with_options(&mut Cursor::new(&bytes), None, windows.clone()).unwrap();
+    assert_eq!(read.get_line_entry("key").unwrap(), "Café");
+
+    let err = DctMap::from_reader(&mut Cursor::new(&bytes))
+      .err()
+      .unwrap()
+      .to_string();
+    let line_id = lookup2(b"key", 0);
+    assert!(err.contains(&format!("line {line_id:#010x}")), "{err}");
+
+    dct_map.add_line_entry("other", "日本").unwrap();
+    assert!(dct_map.to_writer(&mut Cursor::new(Vec::new())).is_err());
+  }
+
+  #[test]
+  fn test_hasher() {
+    let mut dct_map = DctMap::with_hasher(Arc::new(Fnv1a), 0x811c9dc5, 4, vec![]);
+    dct_map.add_line_entry("key", "text").unwrap();
+    let line_id = Fnv1a.hash(b"key", 0x811c9dc5);
+    assert_eq!(dct_map.get_line_entry_by_hash(line_id).unwrap(), "text");
+
+    let mut buf = Cursor::new(Vec::new());
+    dct_map.to_writer(&mut buf).unwrap();
+    buf.set_position(0);
+
+    // files don't record their hash, so keys only resolve once it is set
+    let mut read = DctMap::from_reader(&mut buf).unwrap();
+    assert!(read.get_line_entry("key").is_err());
+    read.set_hasher(Arc::new(Fnv1a));
+    assert_eq!(read.get_line_entry("key").unwrap(), "text");
+  }
 }
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+/// How the strings of a dct are stored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TextEncoding {
+  /// Strict UTF-8, which PC builds use
+  #[default]
+  Utf8,
+  /// A Windows code page from 1250 to 1258, which some regional builds use
+  Windows(u16),
+  /// A single byte encoding with a game specific glyph table
+  Custom(CustomEncoding),
+}
+
+/// Maps every byte to a character, starting from ASCII.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomEncoding {
+  chars: HashMap<u8, char>,
+  bytes: HashMap<char, u8>,
+}
+
+/// Where a string is stored, to report it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextLocation {
+  Line(u32),
+  FooterEntry(usize),
+  FooterSubEntry(usize, usize),
+}
+
+#[derive(Error, Debug)]
+pub enum TextError {
+  #[error("{location} isn't valid {encoding}: {lossy:?}")]
+  Undecodable {
+    location: TextLocation,
+    /// The name of the encoding, as custom tables are too large to keep in every error
+    encoding: String,
+    /// The text with every invalid byte replaced by U+FFFD
+    lossy: String,
+  },
+  #[error("{location} has characters that {encoding} can't encode: {text:?}")]
+  Unencodable {
+    location: TextLocation,
+    encoding: String,
+    text: String,
+  },
+  #[error("unknown encoding {0}, expected utf-8 or windows-1250 to windows-1258")]
+  UnknownEncoding(String),
+}
+
+impl Display for TextLocation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TextLocation::Line(line_id) => write!(f, "line {line_id:#010x}"),
+      TextLocation::FooterEntry(entry) => write!(f, "footer entry {entry}"),
+      TextLocation::FooterSubEntry(entry, sub_entry) => {
+        write!(f, "sub entry {sub_entry} of footer entry {entry}")
+      }
+    }
+  }
+}
+
+impl Display for TextEncoding {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TextEncoding::Utf8 => f.write_str("utf-8"),
+      TextEncoding::Windows(code_page) => write!(f, "windows-{code_page}"),
+      TextEncoding::Custom(_) => f.write_str("custom encoding"),
+    }
+  }
+}
+
+impl TextEncoding {
+  /// Parses `utf-8` or `windows-1250` to `windows-1258`, also written as `cp1252`.
+  pub fn from_label(label: &str) -> Result<Self, TextError> {
+    let label = label.to_ascii_lowercase();
+    if label == "utf-8" || label == "utf8" {
+      return Ok(TextEncoding::Utf8);
+    }
+
+    let code_page = label
+      .strip_prefix("windows-")
+      .or_else(|| label.strip_prefix("cp"))
+      .and_then(|code_page| code_page.parse().ok())
+      .filter(|code_page| (1250..=1258).contains(code_page));
+
+    match code_page {
+      Some(code_page) => Ok(TextEncoding::Windows(code_page)),
+      None => Err(TextError::UnknownEncoding(label)),
+    }
+  }
+
+  fn code_page(code_page: u16) -> &'static encoding_rs::Encoding {
+    encoding_rs::Encoding::for_label(format!("windows-{code_page}").as_bytes())
+      .expect("code pages are checked when they are parsed")
+  }
+
+  pub fn decode(&self, bytes: &[u8], location: TextLocation) -> Result<String, TextError> {
+    let text = match self {
+      TextEncoding::Utf8 => std::str::from_utf8(bytes).ok().map(str::to_string),
+      TextEncoding::Windows(code_page) => Self::code_page(*code_page)
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned()),
+      TextEncoding::Custom(custom) => bytes
+        .iter()
+        .map(|byte| custom.chars.get(byte))
+        .collect::<Option<String>>(),
+    };
+
+    text.ok_or_else(|| TextError::Undecodable {
+      location,
+      encoding: self.to_string(),
+      lossy: self.decode_lossy(bytes),
+    })
+  }
+
+  fn decode_lossy(&self, bytes: &[u8]) -> String {
+    match self {
+      TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+      TextEncoding::Windows(code_page) => Self::code_page(*code_page)
+        .decode_without_bom_handling(bytes)
+        .0
+        .into_owned(),
+      TextEncoding::Custom(custom) => bytes
+        .iter()
+        .map(|byte| {
+          custom
+            .chars
+            .get(byte)
+            .copied()
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+        .collect(),
+    }
+  }
+
+  /// Encodes `text`, which may not contain NUL as it ends strings.
+  pub fn encode(&self, text: &str, location: TextLocation) -> Result<Vec<u8>, TextError> {
+    let bytes = match self {
+      _ if text.contains('\0') => None,
+      TextEncoding::Utf8 => Some(text.as_bytes().to_vec()),
+      TextEncoding::Windows(code_page) => {
+        let (bytes, _, had_errors) = Self::code_page(*code_page).encode(text);
+        (!had_errors).then(|| bytes.into_owned())
+      }
+      TextEncoding::Custom(custom) => text
+        .chars()
+        .map(|c| custom.bytes.get(&c).copied())
+        .collect(),
+    };
+
+    bytes.ok_or_else(|| TextError::Unencodable {
+      location,
+      encoding: self.to_string(),
+      text: text.to_string(),
+    })
+  }
+}
+
+impl CustomEncoding {
+  /// Starts from ASCII and maps every byte of `mappings` to its character instead. NUL can't be
+  /// mapped, as it ends strings.
+  pub fn new(mappings: impl IntoIterator<Item = (u8, char)>) -> Self {
+    let mut chars: HashMap<_, _> = (1..0x80).map(|byte| (byte, char::from(byte))).collect();
+    chars.extend(mappings.into_iter().filter(|(byte, _)| *byte != 0));
+
+    // characters of several bytes are encoded as the lowest one
+    let mut sorted: Vec<_> = chars.iter().map(|(byte, c)| (*byte, *c)).collect();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let bytes = sorted.into_iter().map(|(byte, c)| (c, byte)).collect();
+
+    Self { chars, bytes }
+  }
+
+  /// The bytes that don't map to their ASCII character.
+  pub fn mappings(&self) -> impl Iterator<Item = (u8, char)> + '_ {
+    self
+      .chars
+      .iter()
+      .filter(|(byte, c)| !byte.is_ascii() || char::from(**byte) != **c)
+      .map(|(byte, c)| (*byte, *c))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::encoding::{CustomEncoding, TextEncoding, TextError, TextLocation};
+
+  #[test]
+  fn test_encodings() {
+    let location = TextLocation::Line(0x1a2b);
+    let windows = TextEncoding::from_label("windows-1252").unwrap();
+
+    assert_eq!(windows.encode("Café", location).unwrap(), b"Caf\xe9");
+    assert_eq!(windows.decode(b"Caf\xe9", location).unwrap(), "Café");
+    assert!(windows.encode("日本", location).is_err());
+    assert!(TextEncoding::Utf8.encode("a\0b", location).is_err());
+
+    let err = TextEncoding::Utf8.decode(b"Caf\xe9", location).unwrap_err();
+    assert!(matches!(err, TextError::Undecodable { .. }));
+    assert_eq!(
+      err.to_string(),
+      "line 0x00001a2b isn't valid utf-8: \"Caf\u{fffd}\""
+    );
+
+    let custom = TextEncoding::Custom(CustomEncoding::new([(0x80, '♥')]));
+    assert_eq!(custom.encode("a♥", location).unwrap(), b"a\x80");
+    assert_eq!(custom.decode(b"a\x80", location).unwrap(), "a♥");
+    assert!(custom.decode(b"\x81", location).is_err());
+
+    assert_eq!(
+      TextEncoding::from_label("CP1251").unwrap(),
+      TextEncoding::Windows(1251)
+    );
+    assert!(TextEncoding::from_label("windows-1259").is_err());
+  }
+}
@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use jenkins_hash::lookup2;
+
+/// Hashes the keys of a dct to the line ids its lines are stored under.
+///
+/// The file doesn't record its hash, only the initial hash value every key is hashed with, so it
+/// has to be known from the title the dct belongs to.
+pub trait DctHasher: Debug + Send + Sync {
+  /// Name the hash is recorded and selected by, see [`hasher_by_name`].
+  fn name(&self) -> &str;
+
+  fn hash(&self, key: &[u8], initial_hash_value: u32) -> u32;
+}
+
+/// Bob Jenkins' lookup2, which every known dct uses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lookup2;
+
+impl DctHasher for Lookup2 {
+  fn name(&self) -> &str {
+    "lookup2"
+  }
+
+  fn hash(&self, key: &[u8], initial_hash_value: u32) -> u32 {
+    lookup2(key, initial_hash_value)
+  }
+}
+
+/// The hasher [`crate::dct_map::DctMap`] uses unless another one is selected.
+pub fn default_hasher() -> Arc<dyn DctHasher> {
+  Arc::new(Lookup2)
+}
+
+/// Names of the hashers this crate implements, more are added once titles using them are known.
+pub const HASHER_NAMES: [&str; 1] = ["lookup2"];
+
+/// The hasher of this crate with [`DctHasher::name`] `name`.
+pub fn hasher_by_name(name: &str) -> Option<Arc<dyn DctHasher>> {
+  match name {
+    "lookup2" => Some(Arc::new(Lookup2)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use crate::hasher::{hasher_by_name, DctHasher, HASHER_NAMES};
+
+  /// 32 bit FNV-1a, with the initial hash value as its offset basis, to test maps with another
+  /// hasher than the default.
+  #[derive(Clone, Copy, Debug, Default)]
+  pub(crate) struct Fnv1a;
+
+  impl DctHasher for Fnv1a {
+    fn name(&self) -> &str {
+      "fnv1a"
+    }
+
+    fn hash(&self, key: &[u8], initial_hash_value: u32) -> u32 {
+      key.iter().fold(initial_hash_value, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+      })
+    }
+  }
+
+  #[test]
+  fn test_hashers() {
+    // the offset basis of FNV-1a, and its test vector for "a"
+    assert_eq!(Fnv1a.hash(b"a", 0x811c9dc5), 0xe40c292c);
+
+    for name in HASHER_NAMES {
+      assert_eq!(hasher_by_name(name).unwrap().name(), name);
+    }
+    assert!(hasher_by_name("fnv1a").is_none());
+  }
+}
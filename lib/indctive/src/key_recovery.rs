@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::dct_map::DctMap;
+use crate::hasher::DctHasher;
+
+/// Finds the keys of a dct map without its stringids file, by hashing candidate keys and matching
+/// them against the line ids of the map.
+pub struct KeyRecovery {
+  hasher: Arc<dyn DctHasher>,
+  initial_hash_value: u32,
+  unresolved: BTreeSet<u32>,
+  resolved: BTreeMap<u32, String>,
+}
+
+impl KeyRecovery {
+  pub fn new(dct_map: &DctMap) -> Self {
+    Self {
+      hasher: dct_map.get_hasher().clone(),
+      initial_hash_value: dct_map.get_initial_hash_value(),
+      unresolved: dct_map
+        .iter_line_entries()
+        .map(|(line_id, _)| line_id)
+        .collect(),
+      resolved: BTreeMap::new(),
+    }
+  }
+
+  /// Hashes `key` and records it if it belongs to an unresolved line, returning whether it did.
+  pub fn try_key(&mut self, key: &str) -> bool {
+    let line_id = self.hasher.hash(key.as_bytes(), self.initial_hash_value);
+    if !self.unresolved.remove(&line_id) {
+      return false;
+    }
+
+    self.resolved.insert(line_id, key.to_string());
+    true
+  }
+
+  pub fn try_keys<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, keys: I) -> usize {
+    keys
+      .into_iter()
+      .filter(|key| self.try_key(key.as_ref()))
+      .count()
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.unresolved.is_empty()
+  }
+
+  /// Line ids with their recovered key.
+  pub fn resolved(&self) -> &BTreeMap<u32, String> {
+    &self.resolved
+  }
+
+  /// Line ids no candidate key hashed to.
+  pub fn unresolved(&self) -> &BTreeSet<u32> {
+    &self.unresolved
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::dct_map::DctMap;
+  use crate::key_recovery::KeyRecovery;
+
+  #[test]
+  fn test_recover_keys() {
+    let mut dct_map = DctMap::new(0x1FEDBEEF, 30, vec![]);
+    dct_map.add_line_entry("MENU_START", "Start").unwrap();
+    dct_map.add_line_entry("MENU_QUIT", "Quit").unwrap();
+
+    let mut recovery = KeyRecovery::new(&dct_map);
+    assert_eq!(
+      recovery.try_keys(["MENU_START", "MENU_OPTIONS", "MENU_START"]),
+      1
+    );
+    assert!(!recovery.is_complete());
+
+    let start = dct_map.hash_key("MENU_START");
+    assert_eq!(recovery.resolved()[&start], "MENU_START");
+    assert!(recovery
+      .unresolved()
+      .contains(&dct_map.hash_key("MENU_QUIT")));
+
+    assert!(recovery.try_key("MENU_QUIT"));
+    assert!(recovery.is_complete());
+  }
+}
@@ -1,3 +1,8 @@
+pub use binrw::Endian;
+
 mod bin_parser_writer;
 mod bin_structure;
 pub mod dct_map;
+pub mod encoding;
+pub mod hasher;
+pub mod key_recovery;
@@ -0,0 +1,182 @@
+use indexmap::IndexMap;
+
+use crate::{ContainerData, Data};
+
+#[derive(Debug)]
+pub enum Change<'a> {
+  Added(&'a Data),
+  Removed(&'a Data),
+  Modified(&'a Data, &'a Data),
+}
+
+#[derive(Debug)]
+pub struct Difference<'a> {
+  pub path: String,
+  pub change: Change<'a>,
+}
+
+/// Compares two decoded trees and lists every changed node by its path.
+///
+/// Repeated siblings (`ContainerData::Multiple`) are compared by position and addressed as `key[i]`.
+pub fn diff<'a>(
+  old: &'a IndexMap<String, ContainerData>,
+  new: &'a IndexMap<String, ContainerData>,
+) -> Vec<Difference<'a>> {
+  let mut differences = Vec::new();
+  diff_containers(&mut differences, "", old, new);
+  differences
+}
+
+fn diff_containers<'a>(
+  differences: &mut Vec<Difference<'a>>,
+  path: &str,
+  old: &'a IndexMap<String, ContainerData>,
+  new: &'a IndexMap<String, ContainerData>,
+) {
+  for (key, old_data) in old {
+    let old_list = old_data.as_slice();
+    let new_list = new.get(key).map(ContainerData::as_slice).unwrap_or(&[]);
+    diff_lists(differences, &join_path(path, key), old_list, new_list);
+  }
+
+  for (key, new_data) in new {
+    if !old.contains_key(key) {
+      diff_lists(differences, &join_path(path, key), &[], new_data.as_slice());
+    }
+  }
+}
+
+fn diff_lists<'a>(
+  differences: &mut Vec<Difference<'a>>,
+  path: &str,
+  old: &'a [Data],
+  new: &'a [Data],
+) {
+  let indexed = old.len() > 1 || new.len() > 1;
+
+  for i in 0..old.len().max(new.len()) {
+    let path = if indexed {
+      format!("{path}[{i}]")
+    } else {
+      path.to_string()
+    };
+
+    match (old.get(i), new.get(i)) {
+      (Some(old), Some(new)) => diff_data(differences, &path, old, new),
+      (Some(old), None) => differences.push(Difference {
+        path,
+        change: Change::Removed(old),
+      }),
+      (None, Some(new)) => differences.push(Difference {
+        path,
+        change: Change::Added(new),
+      }),
+      (None, None) => unreachable!(),
+    }
+  }
+}
+
+fn diff_data<'a>(differences: &mut Vec<Difference<'a>>, path: &str, old: &'a Data, new: &'a Data) {
+  match (old, new) {
+    (Data::Container(old), Data::Container(new)) => diff_containers(differences, path, old, new),
+    (old, new) if !data_eq(old, new) => differences.push(Difference {
+      path: path.to_string(),
+      change: Change::Modified(old, new),
+    }),
+    _ => {}
+  }
+}
+
+pub(crate) fn join_path(path: &str, key: &str) -> String {
+  if path.is_empty() {
+    key.to_string()
+  } else {
+    format!("{path}/{key}")
+  }
+}
+
+pub(crate) fn list_eq(a: &[Data], b: &[Data]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).all(|(a, b)| data_eq(a, b))
+}
+
+/// Structural equality, treating NaN floats as equal to each other.
+pub(crate) fn data_eq(a: &Data, b: &Data) -> bool {
+  fn float_eq(a: f32, b: f32) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+  }
+
+  match (a, b) {
+    (Data::Container(a), Data::Container(b)) => {
+      a.len() == b.len()
+        && a.iter().all(|(key, a)| match b.get(key) {
+          Some(b) => list_eq(a.as_slice(), b.as_slice()),
+          None => false,
+        })
+    }
+    (Data::Binary(a), Data::Binary(b)) => a == b,
+    (Data::Uuid(a), Data::Uuid(b)) => a == b,
+    (Data::Int(a), Data::Int(b)) => a == b,
+    (Data::IntVec(a), Data::IntVec(b)) => a == b,
+    (Data::Float(a), Data::Float(b)) => float_eq(*a, *b),
+    (Data::FloatVec(a), Data::FloatVec(b)) => {
+      a.len() == b.len() && a.iter().zip(b).all(|(a, b)| float_eq(*a, *b))
+    }
+    (Data::String(a), Data::String(b)) => a == b,
+    (Data::StringVec(a), Data::StringVec(b)) => a == b,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indexmap::IndexMap;
+
+  use crate::diff::{diff, Change};
+  use crate::{ContainerData, Data};
+
+  #[test]
+  fn test() {
+    let mut old = IndexMap::new();
+    old.insert("Speed".to_string(), ContainerData::Single(Data::Float(1.0)));
+    old.insert(
+      "Nan".to_string(),
+      ContainerData::Single(Data::Float(f32::NAN)),
+    );
+    old.insert(
+      "Wheel".to_string(),
+      ContainerData::Multiple(vec![Data::Int(1), Data::Int(2)]),
+    );
+
+    let mut new = IndexMap::new();
+    new.insert(
+      "Nan".to_string(),
+      ContainerData::Single(Data::Float(f32::NAN)),
+    );
+    new.insert(
+      "Wheel".to_string(),
+      ContainerData::Multiple(vec![Data::Int(1), Data::Int(3), Data::Int(4)]),
+    );
+    new.insert(
+      "Name".to_string(),
+      ContainerData::Single(Data::String("car".to_string())),
+    );
+
+    let differences = diff(&old, &new);
+    let paths: Vec<_> = differences.iter().map(|x| x.path.as_str()).collect();
+    assert_eq!(paths, ["Speed", "Wheel[1]", "Wheel[2]", "Name"]);
+
+    assert!(matches!(
+      differences[0].change,
+      Change::Removed(Data::Float(_))
+    ));
+    assert!(matches!(
+      differences[1].change,
+      Change::Modified(Data::Int(2), Data::Int(3))
+    ));
+    assert!(matches!(differences[2].change, Change::Added(Data::Int(4))));
+    assert!(matches!(
+      differences[3].change,
+      Change::Added(Data::String(_))
+    ));
+  }
+}
@@ -1,24 +1,62 @@
 use anyhow::anyhow;
 pub use binrw::Endian;
 use binrw::{BinReaderExt, BinWriterExt, NullString};
-use indexmap::{IndexMap, IndexSet};
+use indexmap::IndexMap;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::io::{Read, Seek, SeekFrom, Write};
 use uuid::Uuid;
 
+pub use crate::diff::{diff, Change, Difference};
 use crate::header::OctHeader;
+pub use crate::merge::{merge, Conflict, Merge};
 use crate::node::{Node, NodeData, RawNode};
+pub use crate::schema::{key_pattern, DataType, Schema, SchemaField, SchemaNode, Violation};
+use crate::string_table::StringTable;
+use crate::validate::validate;
 
+mod diff;
 mod header;
+mod merge;
 mod node;
+mod schema;
+mod string_table;
+mod validate;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum ContainerData {
   Single(Data),
   Multiple(Vec<Data>),
 }
 
+impl ContainerData {
+  pub fn as_slice(&self) -> &[Data] {
+    match self {
+      ContainerData::Single(data) => std::slice::from_ref(data),
+      ContainerData::Multiple(data) => data,
+    }
+  }
+
+  pub fn as_mut_slice(&mut self) -> &mut [Data] {
+    match self {
+      ContainerData::Single(data) => std::slice::from_mut(data),
+      ContainerData::Multiple(data) => data,
+    }
+  }
+
+  pub fn from_vec(mut data: Vec<Data>) -> Self {
+    if data.len() == 1 {
+      ContainerData::Single(data.remove(0))
+    } else {
+      ContainerData::Multiple(data)
+    }
+  }
+
+  pub fn from_slice(data: &[Data]) -> Self {
+    Self::from_vec(data.to_vec())
+  }
+}
+
 fn deserialize_f64_null_as_nan<'de, D: Deserializer<'de>>(des: D) -> Result<f32, D::Error> {
   let optional = Option::<f32>::deserialize(des)?;
   Ok(optional.unwrap_or(f32::NAN))
@@ -35,7 +73,7 @@ fn deserialize_vec_f64_null_as_nan<'de, D: Deserializer<'de>>(
   )
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Data {
   Container(IndexMap<String, ContainerData>),
@@ -74,16 +112,37 @@ mod base64 {
   }
 }
 
+fn endian_of(magic: &[u8; 8]) -> Option<Endian> {
+  match magic {
+    [0x29, 0x76, 0x01, 0x45, 0xcd, 0xcc, 0x8c, 0x3f] => Some(Endian::Little),
+    [0x45, 0x01, 0x76, 0x29, 0x3f, 0x8c, 0xcc, 0xcd] => Some(Endian::Big),
+    _ => None,
+  }
+}
+
+/// Checks for the magic of a Tupperware file, leaving `read` at its start.
+pub fn is_tupperware<R: Read + Seek>(read: &mut R) -> anyhow::Result<bool> {
+  let start = read.stream_position()?;
+
+  let mut magic: [u8; 8] = [0u8; 8];
+  let is_tupperware = match read.read_exact(&mut magic) {
+    Ok(()) => endian_of(&magic).is_some(),
+    Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => false,
+    Err(err) => return Err(err.into()),
+  };
+
+  read.seek(SeekFrom::Start(start))?;
+  Ok(is_tupperware)
+}
+
 pub fn decode<R: Read + Seek>(
   read: &mut R,
 ) -> anyhow::Result<(IndexMap<String, ContainerData>, Endian)> {
   let mut magic: [u8; 8] = [0u8; 8];
   read.read_exact(&mut magic)?;
 
-  let endian = match magic {
-    [0x29, 0x76, 0x01, 0x45, 0xcd, 0xcc, 0x8c, 0x3f] => Endian::Little,
-    [0x45, 0x01, 0x76, 0x29, 0x3f, 0x8c, 0xcc, 0xcd] => Endian::Big,
-    _ => return Err(anyhow!("Invalid magic: {magic:x?}")),
+  let Some(endian) = endian_of(&magic) else {
+    return Err(anyhow!("Invalid magic: {magic:x?}"));
   };
 
   let header: OctHeader = read.read_type(endian)?;
@@ -140,6 +199,8 @@ pub fn encode<R: Write + Seek>(
   data: IndexMap<String, ContainerData>,
   endian: Endian,
 ) -> anyhow::Result<()> {
+  validate(&data)?;
+
   let mut nodes = Vec::new();
   nodes.push(RawNode {
     level: 0,
@@ -150,22 +211,22 @@ pub fn encode<R: Write + Seek>(
   });
   extract_nodes(&mut nodes, data, 1);
 
-  let mut strings = IndexSet::new();
+  let mut strings = StringTable::default();
   for RawNode { node, .. } in &nodes {
     if let Some((key, name)) = node.id.split_once('#') {
-      strings.insert(key.to_string());
-      strings.insert(name.to_string());
+      strings.insert(key)?;
+      strings.insert(name)?;
     } else {
-      strings.insert(node.id.to_string());
+      strings.insert(&node.id)?;
     }
 
     match &node.data {
       NodeData::String(data) => {
-        strings.insert(data.to_string());
+        strings.insert(data)?;
       }
       NodeData::StringVec(data) => {
         for x in data {
-          strings.insert(x.to_string());
+          strings.insert(x)?;
         }
       }
 
@@ -175,8 +236,6 @@ pub fn encode<R: Write + Seek>(
     }
   }
 
-  let strings = strings.into_iter().collect::<Vec<_>>();
-
   write.write_type(
     match endian {
       Endian::Big => b"\x45\x01\x76\x29\x3f\x8c\xcc\xcd",
@@ -189,14 +248,14 @@ pub fn encode<R: Write + Seek>(
   write.seek(SeekFrom::Current(12 + 40))?;
 
   let start = write.stream_position()?;
-  for x in &strings {
-    write.write_type(&NullString::from(x.as_str()), endian)?;
+  for x in strings.strings() {
+    write.write_type(&NullString::from(x), endian)?;
   }
   let string_size = write.stream_position()? - start;
 
   let start = write.stream_position()?;
   for x in &nodes {
-    write.write_type_args(x, endian, strings.as_slice())?;
+    write.write_type_args(x, endian, &strings)?;
   }
   let node_size = write.stream_position()? - start;
 
@@ -0,0 +1,184 @@
+use indexmap::IndexMap;
+
+use crate::diff::{data_eq, join_path, list_eq};
+use crate::{ContainerData, Data};
+
+#[derive(Debug)]
+pub struct Conflict {
+  pub path: String,
+  pub base: Option<ContainerData>,
+  pub ours: Option<ContainerData>,
+  pub theirs: Option<ContainerData>,
+}
+
+#[derive(Debug)]
+pub struct Merge {
+  pub data: IndexMap<String, ContainerData>,
+  pub conflicts: Vec<Conflict>,
+}
+
+/// Three-way merges two edited trees against their common base.
+///
+/// Nodes changed on only one side take that side's value. Nodes changed differently on both sides
+/// are reported as conflicts and keep our value in the merged tree.
+pub fn merge(
+  base: &IndexMap<String, ContainerData>,
+  ours: &IndexMap<String, ContainerData>,
+  theirs: &IndexMap<String, ContainerData>,
+) -> Merge {
+  let mut conflicts = Vec::new();
+  let data = merge_containers(&mut conflicts, "", base, ours, theirs);
+  Merge { data, conflicts }
+}
+
+fn merge_containers(
+  conflicts: &mut Vec<Conflict>,
+  path: &str,
+  base: &IndexMap<String, ContainerData>,
+  ours: &IndexMap<String, ContainerData>,
+  theirs: &IndexMap<String, ContainerData>,
+) -> IndexMap<String, ContainerData> {
+  let mut merged = IndexMap::new();
+
+  let keys = ours
+    .keys()
+    .chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+
+  for key in keys {
+    let merged_data = merge_lists(
+      conflicts,
+      &join_path(path, key),
+      base.get(key).map(ContainerData::as_slice),
+      ours.get(key).map(ContainerData::as_slice),
+      theirs.get(key).map(ContainerData::as_slice),
+    );
+
+    if let Some(merged_data) = merged_data {
+      merged.insert(key.clone(), merged_data);
+    }
+  }
+
+  merged
+}
+
+fn merge_lists(
+  conflicts: &mut Vec<Conflict>,
+  path: &str,
+  base: Option<&[Data]>,
+  ours: Option<&[Data]>,
+  theirs: Option<&[Data]>,
+) -> Option<ContainerData> {
+  let eq = |a: Option<&[Data]>, b: Option<&[Data]>| match (a, b) {
+    (Some(a), Some(b)) => list_eq(a, b),
+    (None, None) => true,
+    _ => false,
+  };
+
+  if eq(ours, theirs) || eq(theirs, base) {
+    return ours.map(ContainerData::from_slice);
+  }
+  if eq(ours, base) {
+    return theirs.map(ContainerData::from_slice);
+  }
+
+  if let (Some(base), Some(ours), Some(theirs)) = (base, ours, theirs) {
+    if base.len() == ours.len() && base.len() == theirs.len() {
+      let indexed = base.len() > 1;
+      let merged: Vec<_> = (0..base.len())
+        .map(|i| {
+          let path = if indexed {
+            format!("{path}[{i}]")
+          } else {
+            path.to_string()
+          };
+          merge_data(conflicts, &path, &base[i], &ours[i], &theirs[i])
+        })
+        .collect();
+
+      return Some(ContainerData::from_vec(merged));
+    }
+  }
+
+  conflicts.push(Conflict {
+    path: path.to_string(),
+    base: base.map(ContainerData::from_slice),
+    ours: ours.map(ContainerData::from_slice),
+    theirs: theirs.map(ContainerData::from_slice),
+  });
+  ours.map(ContainerData::from_slice)
+}
+
+fn merge_data(
+  conflicts: &mut Vec<Conflict>,
+  path: &str,
+  base: &Data,
+  ours: &Data,
+  theirs: &Data,
+) -> Data {
+  if data_eq(ours, theirs) || data_eq(theirs, base) {
+    return ours.clone();
+  }
+  if data_eq(ours, base) {
+    return theirs.clone();
+  }
+
+  if let (Data::Container(base), Data::Container(ours), Data::Container(theirs)) =
+    (base, ours, theirs)
+  {
+    return Data::Container(merge_containers(conflicts, path, base, ours, theirs));
+  }
+
+  conflicts.push(Conflict {
+    path: path.to_string(),
+    base: Some(ContainerData::Single(base.clone())),
+    ours: Some(ContainerData::Single(ours.clone())),
+    theirs: Some(ContainerData::Single(theirs.clone())),
+  });
+  ours.clone()
+}
+
+#[cfg(test)]
+mod tests {
+  use indexmap::IndexMap;
+
+  use crate::merge::merge;
+  use crate::{ContainerData, Data};
+
+  fn tree(entries: &[(&str, i32)]) -> IndexMap<String, ContainerData> {
+    let mut children = IndexMap::new();
+    for (key, value) in entries {
+      children.insert(key.to_string(), ContainerData::Single(Data::Int(*value)));
+    }
+
+    let mut root = IndexMap::new();
+    root.insert(
+      "Tuning".to_string(),
+      ContainerData::Single(Data::Container(children)),
+    );
+    root
+  }
+
+  #[test]
+  fn test() {
+    let base = tree(&[("A", 1), ("B", 2), ("C", 3)]);
+    let ours = tree(&[("A", 10), ("B", 2), ("C", 30)]);
+    let theirs = tree(&[("A", 1), ("B", 20), ("C", 31), ("D", 4)]);
+
+    let merged = merge(&base, &ours, &theirs);
+
+    assert_eq!(merged.conflicts.len(), 1);
+    assert_eq!(merged.conflicts[0].path, "Tuning/C");
+
+    let Some(ContainerData::Single(Data::Container(tuning))) = merged.data.get("Tuning") else {
+      panic!("Expected container");
+    };
+    let values: Vec<_> = tuning
+      .iter()
+      .map(|(key, data)| match data {
+        ContainerData::Single(Data::Int(value)) => (key.as_str(), *value),
+        _ => panic!("Expected int"),
+      })
+      .collect();
+    assert_eq!(values, [("A", 10), ("B", 20), ("C", 30), ("D", 4)]);
+  }
+}
@@ -4,8 +4,15 @@ use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt, Endian, Er
 use modular_bitfield::prelude::*;
 use uuid::{Bytes, Uuid};
 
+use crate::string_table::StringTable;
+
 const UUID_KEY: &str = "Uuid";
 
+/// Largest value of the 6 bit `level` header field.
+pub(crate) const MAX_LEVEL: u8 = (1 << 6) - 1;
+/// Largest byte count of the 2 bit `len_size` and `int_size` header fields, which store it -1.
+pub(crate) const MAX_SIZE: u8 = 4;
+
 #[derive(Debug)]
 pub(crate) struct Node {
   pub(crate) id: String,
@@ -169,16 +176,6 @@ impl BinRead for RawNode {
   }
 }
 
-fn find(strings: &[String], string: &str) -> u16 {
-  for (i, str) in strings.iter().enumerate() {
-    if str == string {
-      return i as u16;
-    }
-  }
-
-  panic!("Can't find string");
-}
-
 const fn get_u32_size(i: u32) -> u8 {
   let actual_bits = 32 - i.leading_zeros();
 
@@ -195,16 +192,23 @@ const fn get_u32_size(i: u32) -> u8 {
 }
 
 const fn get_i32_size(i: i32) -> u8 {
-  let actual_bits = 32 - i.abs().leading_zeros() + 1;
+  let actual_bits = 32 - i.unsigned_abs().leading_zeros() + 1;
   // +1 because of signing bit
 
   let bytes_used = actual_bits / 8;
   let bits_remaining = actual_bits % 8;
 
-  (if bits_remaining > 0 {
+  let size = if bits_remaining > 0 {
     bytes_used + 1
   } else {
     bytes_used
+  };
+
+  // only i32::MIN has no positive counterpart, it still fits into 4 bytes
+  (if size > MAX_SIZE as u32 {
+    MAX_SIZE as u32
+  } else {
+    size
   }) as u8
 }
 
@@ -222,11 +226,13 @@ mod tests {
     assert_eq!(get_u32_size(u16::MAX as u32 + 1), 3);
 
     assert_eq!(get_i32_size(291), 2);
+    assert_eq!(get_i32_size(i32::MAX), 4);
+    assert_eq!(get_i32_size(i32::MIN), 4);
   }
 }
 
 impl BinWrite for RawNode {
-  type Args<'a> = &'a [String];
+  type Args<'a> = &'a StringTable;
 
   fn write_options<W: Write + Seek>(
     &self,
@@ -269,24 +275,40 @@ impl BinWrite for RawNode {
       NodeData::Uuid(_) => (DataType::Binary, Type::Scalar),
     };
 
+    let pos = writer.stream_position()?;
+
     let key;
     let name;
 
     if let Some((k, n)) = self.node.id.split_once('#') {
-      key = find(args, k);
-      name = Some(find(args, n));
+      key = args.find(k, pos)?;
+      name = Some(args.find(n, pos)?);
     } else {
-      key = find(args, &self.node.id);
+      key = args.find(&self.node.id, pos)?;
       name = None;
     }
 
+    let out_of_bounds = |field: &str| Error::AssertFail {
+      pos,
+      message: format!(
+        "Header field {field} of node {} is out of bounds.",
+        self.node.id
+      ),
+    };
+
     let mut header = NodeHeader::new();
     header.set_type(r#type);
     header.set_name(name.is_some());
     header.set_data_type(data_type);
-    header.set_len_size(len_size - 1);
-    header.set_int_size(int_size - 1);
-    header.set_level(self.level);
+    header
+      .set_len_size_checked(len_size - 1)
+      .map_err(|_| out_of_bounds("len_size"))?;
+    header
+      .set_int_size_checked(int_size - 1)
+      .map_err(|_| out_of_bounds("int_size"))?;
+    header
+      .set_level_checked(self.level)
+      .map_err(|_| out_of_bounds("level"))?;
 
     let header: u16 = header.into();
 
@@ -298,11 +320,11 @@ impl BinWrite for RawNode {
 
     match &self.node.data {
       NodeData::Container(_) => {}
-      NodeData::String(data) => writer.write_type(&find(args, data), endian)?,
+      NodeData::String(data) => writer.write_type(&args.find(data, pos)?, endian)?,
       NodeData::StringVec(data) => {
         write_u32(writer, data.len() as u32, endian, len_size as usize)?;
         for x in data {
-          writer.write_type(&find(args, x), endian)?;
+          writer.write_type(&args.find(x, pos)?, endian)?;
         }
       }
       NodeData::Float(data) => writer.write_type(data, endian)?,
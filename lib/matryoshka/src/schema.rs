@@ -0,0 +1,349 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::diff::join_path;
+use crate::{ContainerData, Data};
+
+/// The variants of [`Data`], which are stored as different node types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DataType {
+  Container,
+  Binary,
+  Uuid,
+  Int,
+  IntVec,
+  Float,
+  FloatVec,
+  String,
+  StringVec,
+}
+
+impl DataType {
+  pub fn of(data: &Data) -> Self {
+    match data {
+      Data::Container(_) => DataType::Container,
+      Data::Binary(_) => DataType::Binary,
+      Data::Uuid(_) => DataType::Uuid,
+      Data::Int(_) => DataType::Int,
+      Data::IntVec(_) => DataType::IntVec,
+      Data::Float(_) => DataType::Float,
+      Data::FloatVec(_) => DataType::FloatVec,
+      Data::String(_) => DataType::String,
+      Data::StringVec(_) => DataType::StringVec,
+    }
+  }
+
+  fn is_vec(&self) -> bool {
+    matches!(
+      self,
+      DataType::IntVec | DataType::FloatVec | DataType::StringVec
+    )
+  }
+}
+
+/// Everything seen at one position of the tree.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SchemaNode {
+  pub types: BTreeSet<DataType>,
+  #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+  pub children: IndexMap<String, SchemaField>,
+  /// Number of containers merged into `children`
+  #[serde(default, skip_serializing_if = "is_zero")]
+  pub samples: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SchemaField {
+  #[serde(flatten)]
+  pub node: SchemaNode,
+  /// Missing from at least one of the containers
+  pub optional: bool,
+  /// Appeared more than once in a container
+  pub repeated: bool,
+}
+
+/// Schema of a set of Tupperware files, inferred from examples with [`Schema::add`].
+///
+/// Keys with an id, like `Texture#a1b2`, are generalized to `Texture#*`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Schema {
+  pub root: SchemaNode,
+}
+
+/// A node that doesn't match the [`Schema`].
+#[derive(Debug)]
+pub struct Violation {
+  pub path: String,
+  pub message: String,
+}
+
+impl Display for Violation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.path, self.message)
+  }
+}
+
+/// Generalizes keys that carry an id, so all of them share a single field.
+pub fn key_pattern(key: &str) -> String {
+  match key.split_once('#') {
+    Some((kind, _)) => format!("{kind}#*"),
+    None => key.to_string(),
+  }
+}
+
+impl Schema {
+  pub fn add(&mut self, data: &IndexMap<String, ContainerData>) {
+    self.root.types.insert(DataType::Container);
+    self.root.add_container(data);
+  }
+
+  /// Lists every node of `data` that is unknown, missing, repeated or of a type the schema
+  /// hasn't seen at its position.
+  pub fn validate(&self, data: &IndexMap<String, ContainerData>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    self.root.validate_container("", data, &mut violations);
+    violations
+  }
+}
+
+impl SchemaNode {
+  fn add(&mut self, data: &Data) {
+    self.types.insert(DataType::of(data));
+
+    if let Data::Container(container) = data {
+      self.add_container(container);
+    }
+  }
+
+  fn add_container(&mut self, container: &IndexMap<String, ContainerData>) {
+    let counts = count_patterns(container);
+
+    for (pattern, field) in &mut self.children {
+      if !counts.contains_key(pattern) {
+        field.optional = true;
+      }
+    }
+
+    for (key, data) in container {
+      let pattern = key_pattern(key);
+      let count = counts[&pattern];
+
+      let field = self.children.entry(pattern).or_insert_with(|| SchemaField {
+        optional: self.samples > 0,
+        ..Default::default()
+      });
+      field.repeated |= count > 1;
+
+      for data in data.as_slice() {
+        field.node.add(data);
+      }
+    }
+
+    self.samples += 1;
+  }
+
+  fn validate_container(
+    &self,
+    path: &str,
+    container: &IndexMap<String, ContainerData>,
+    violations: &mut Vec<Violation>,
+  ) {
+    let counts = count_patterns(container);
+
+    for (pattern, field) in &self.children {
+      if !field.optional && !counts.contains_key(pattern) {
+        violations.push(Violation {
+          path: join_path(path, pattern),
+          message: "required key is missing".to_string(),
+        });
+      }
+    }
+
+    for (key, data) in container {
+      let path = join_path(path, key);
+      let pattern = key_pattern(key);
+
+      let Some(field) = self.children.get(&pattern) else {
+        let message = match self.closest_key(&pattern) {
+          Some(closest) => format!("unknown key, did you mean {closest}?"),
+          None => "unknown key".to_string(),
+        };
+        violations.push(Violation { path, message });
+        continue;
+      };
+
+      if !field.repeated && counts[&pattern] > 1 {
+        violations.push(Violation {
+          path: path.clone(),
+          message: "key is repeated, but only appears once per container".to_string(),
+        });
+      }
+
+      let list = data.as_slice();
+      for (i, data) in list.iter().enumerate() {
+        let path = if list.len() > 1 {
+          format!("{path}[{i}]")
+        } else {
+          path.clone()
+        };
+
+        field.node.validate(&path, data, violations);
+      }
+    }
+  }
+
+  fn validate(&self, path: &str, data: &Data, violations: &mut Vec<Violation>) {
+    let data_type = DataType::of(data);
+
+    // an empty list can't be told apart from the other list types once decoded from text
+    let is_empty_vec = match data {
+      Data::IntVec(vec) => vec.is_empty(),
+      Data::FloatVec(vec) => vec.is_empty(),
+      Data::StringVec(vec) => vec.is_empty(),
+      _ => false,
+    };
+    let matches =
+      self.types.contains(&data_type) || (is_empty_vec && self.types.iter().any(DataType::is_vec));
+
+    if !matches {
+      violations.push(Violation {
+        path: path.to_string(),
+        message: format!("expected {}, found {data_type:?}", self.describe_types()),
+      });
+      return;
+    }
+
+    if let Data::Container(container) = data {
+      self.validate_container(path, container, violations);
+    }
+  }
+
+  fn describe_types(&self) -> String {
+    let types: Vec<_> = self.types.iter().map(|t| format!("{t:?}")).collect();
+    types.join(" or ")
+  }
+
+  /// Finds the known key that is the fewest edits away, to point out typos.
+  fn closest_key(&self, pattern: &str) -> Option<&str> {
+    self
+      .children
+      .keys()
+      .map(|key| (edit_distance(key, pattern), key))
+      .filter(|(distance, key)| *distance <= (key.len() / 2).max(1))
+      .min_by_key(|(distance, _)| *distance)
+      .map(|(_, key)| key.as_str())
+  }
+}
+
+fn is_zero(samples: &usize) -> bool {
+  *samples == 0
+}
+
+fn count_patterns(container: &IndexMap<String, ContainerData>) -> IndexMap<String, usize> {
+  let mut counts = IndexMap::new();
+  for (key, data) in container {
+    *counts.entry(key_pattern(key)).or_default() += data.as_slice().len();
+  }
+  counts
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<_> = b.chars().collect();
+  let mut previous: Vec<_> = (0..=b.len()).collect();
+
+  for (i, a) in a.chars().enumerate() {
+    let mut current = vec![i + 1];
+    for (j, b) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a != *b);
+      current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+    }
+    previous = current;
+  }
+
+  previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+  use indexmap::IndexMap;
+
+  use crate::schema::{edit_distance, key_pattern, Schema};
+  use crate::{ContainerData, Data};
+
+  fn container(entries: Vec<(&str, ContainerData)>) -> IndexMap<String, ContainerData> {
+    entries
+      .into_iter()
+      .map(|(key, data)| (key.to_string(), data))
+      .collect()
+  }
+
+  #[test]
+  fn test_infer_and_validate() {
+    let texture = |id: &str, size: Option<i32>| {
+      let mut texture = container(vec![(
+        "Name",
+        ContainerData::Single(Data::String(id.to_string())),
+      )]);
+      if let Some(size) = size {
+        texture.insert("Size".to_string(), ContainerData::Single(Data::Int(size)));
+      }
+      (
+        format!("Texture#{id}"),
+        ContainerData::Single(Data::Container(texture)),
+      )
+    };
+
+    let mut schema = Schema::default();
+    schema.add(&IndexMap::from([texture("a", Some(4)), texture("b", None)]));
+    schema.add(&IndexMap::from([texture("c", Some(8))]));
+
+    let field = &schema.root.children["Texture#*"];
+    assert!(field.repeated);
+    assert!(!field.optional);
+    assert!(!field.node.children["Name"].optional);
+    assert!(field.node.children["Size"].optional);
+
+    assert!(schema
+      .validate(&IndexMap::from([texture("d", Some(1))]))
+      .is_empty());
+
+    let mut typo = IndexMap::from([texture("e", None)]);
+    let Data::Container(texture) = typo[0].as_mut_slice().first_mut().unwrap() else {
+      unreachable!()
+    };
+    let name = texture.shift_remove("Name").unwrap();
+    texture.insert("Nmae".to_string(), name);
+    texture.insert("Size".to_string(), ContainerData::Single(Data::Float(1.0)));
+
+    let violations: Vec<_> = schema
+      .validate(&typo)
+      .iter()
+      .map(|v| v.to_string())
+      .collect();
+    assert_eq!(
+      violations,
+      [
+        "Texture#e/Name: required key is missing",
+        "Texture#e/Nmae: unknown key, did you mean Name?",
+        "Texture#e/Size: expected Int, found Float",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_key_pattern() {
+    assert_eq!(key_pattern("Texture#a\\b.tga"), "Texture#*");
+    assert_eq!(key_pattern("Data"), "Data");
+  }
+
+  #[test]
+  fn test_edit_distance() {
+    assert_eq!(edit_distance("Name", "Name"), 0);
+    assert_eq!(edit_distance("Name", "Nmae"), 2);
+    assert_eq!(edit_distance("Size", "Sizes"), 1);
+    assert_eq!(edit_distance("", "abc"), 3);
+  }
+}
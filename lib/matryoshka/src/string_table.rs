@@ -0,0 +1,67 @@
+use anyhow::anyhow;
+use binrw::{BinResult, Error};
+use indexmap::IndexMap;
+
+/// Deduplicated strings of a Tupperware file, mapped to their index in the string table.
+#[derive(Default)]
+pub(crate) struct StringTable {
+  indices: IndexMap<String, u16>,
+}
+
+impl StringTable {
+  pub(crate) fn insert(&mut self, string: &str) -> anyhow::Result<()> {
+    if self.indices.contains_key(string) {
+      return Ok(());
+    }
+
+    let index = u16::try_from(self.indices.len()).map_err(|_| {
+      anyhow!(
+        "String table exceeds {} entries, can't index \"{string}\"",
+        u16::MAX as usize + 1
+      )
+    })?;
+
+    self.indices.insert(string.to_string(), index);
+    Ok(())
+  }
+
+  pub(crate) fn find(&self, string: &str, pos: u64) -> BinResult<u16> {
+    self
+      .indices
+      .get(string)
+      .copied()
+      .ok_or_else(|| Error::AssertFail {
+        pos,
+        message: format!("Can't find string \"{string}\" in string table."),
+      })
+  }
+
+  pub(crate) fn strings(&self) -> impl Iterator<Item = &str> {
+    self.indices.keys().map(String::as_str)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::string_table::StringTable;
+
+  #[test]
+  fn test() {
+    let mut table = StringTable::default();
+    table.insert("a").unwrap();
+    table.insert("b").unwrap();
+    table.insert("a").unwrap();
+
+    assert_eq!(table.find("a", 0).unwrap(), 0);
+    assert_eq!(table.find("b", 0).unwrap(), 1);
+    assert!(table.find("c", 0).is_err());
+    assert_eq!(table.strings().collect::<Vec<_>>(), ["a", "b"]);
+
+    let mut table = StringTable::default();
+    for i in 0..=u16::MAX as u32 {
+      table.insert(&i.to_string()).unwrap();
+    }
+    assert!(table.insert("overflow").is_err());
+    assert!(table.insert("0").is_ok());
+  }
+}
@@ -0,0 +1,101 @@
+use anyhow::anyhow;
+use indexmap::IndexMap;
+
+use crate::diff::join_path;
+use crate::node::MAX_LEVEL;
+use crate::{ContainerData, Data};
+
+/// Checks that every node fits into the bitfields of its header, so nothing gets truncated.
+pub(crate) fn validate(data: &IndexMap<String, ContainerData>) -> anyhow::Result<()> {
+  validate_container("", data, 1)
+}
+
+fn validate_container(
+  path: &str,
+  data: &IndexMap<String, ContainerData>,
+  level: u8,
+) -> anyhow::Result<()> {
+  for (key, data) in data {
+    let path = join_path(path, key);
+    let list = data.as_slice();
+
+    for (i, data) in list.iter().enumerate() {
+      let path = if list.len() > 1 {
+        format!("{path}[{i}]")
+      } else {
+        path.clone()
+      };
+
+      if level > MAX_LEVEL {
+        return Err(anyhow!(
+          "Node {path} is nested {level} levels deep, at most {MAX_LEVEL} are supported"
+        ));
+      }
+
+      validate_data(&path, data, level)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn validate_data(path: &str, data: &Data, level: u8) -> anyhow::Result<()> {
+  let len = match data {
+    Data::Container(children) => return validate_container(path, children, level + 1),
+    Data::IntVec(data) => data.len(),
+    Data::StringVec(data) => data.len(),
+    Data::FloatVec(data) => data.len(),
+    Data::Binary(data) => data.len(),
+    // every i32 fits into the 4 bytes of `int_size`
+    Data::Int(_) | Data::Uuid(_) | Data::Float(_) | Data::String(_) => return Ok(()),
+  };
+
+  if u32::try_from(len).is_err() {
+    return Err(anyhow!(
+      "Node {path} has {len} elements, at most {} are supported",
+      u32::MAX
+    ));
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use indexmap::IndexMap;
+
+  use crate::validate::validate;
+  use crate::{ContainerData, Data};
+
+  fn nested(depth: usize) -> IndexMap<String, ContainerData> {
+    let mut data = IndexMap::new();
+    data.insert("Leaf".to_string(), ContainerData::Single(Data::Int(1)));
+
+    for _ in 1..depth {
+      let mut parent = IndexMap::new();
+      parent.insert(
+        "Child".to_string(),
+        ContainerData::Single(Data::Container(data)),
+      );
+      data = parent;
+    }
+
+    data
+  }
+
+  #[test]
+  fn test() {
+    assert!(validate(&nested(63)).is_ok());
+
+    let err = validate(&nested(64)).unwrap_err().to_string();
+    assert!(err.starts_with("Node Child/"), "{err}");
+    assert!(err.ends_with("/Leaf is nested 64 levels deep, at most 63 are supported"));
+
+    let mut data = IndexMap::new();
+    data.insert(
+      "Min".to_string(),
+      ContainerData::Single(Data::IntVec(vec![0, i32::MIN, i32::MAX])),
+    );
+    assert!(validate(&data).is_ok());
+  }
+}
@@ -1,8 +1,10 @@
+pub use crate::repack::*;
 pub use crate::soi::*;
 pub use crate::soi_soup::*;
 pub use crate::str::*;
 pub use crate::toc::*;
 
+mod repack;
 mod soi;
 mod soi_soup;
 mod str;
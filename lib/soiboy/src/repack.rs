@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use binrw::{io, BinResult};
+
+use crate::soi::{read_page_sizes, rewrite_pages};
+use crate::{ComponentData, SectionPages, Str, StrWriter, Toc, DEFAULT_CHUNK_SIZE};
+
+/// The files of a streaming package.
+pub struct PackagePaths<'a> {
+  pub toc: &'a Path,
+  pub soi: &'a Path,
+  pub str: &'a Path,
+}
+
+/// Writes the package of `input` to `output`, with the data `replace` returns for a component
+/// of a section instead of its own, and returns how many components were replaced. An error of
+/// `replace` is returned as is, leaving the output incomplete.
+///
+/// Every section is recompressed into chunks of the largest page size of the soi, so the toc and
+/// the page sizes of the soi are rewritten with it.
+/// Pages are understood as the zlib chunks of every section, numbered from their page offsets, so
+/// packages laid out differently are refused instead of being written wrong.
+pub fn repack<F>(input: &PackagePaths, output: &PackagePaths, mut replace: F) -> BinResult<usize>
+where
+  F: FnMut(u32, &ComponentData) -> io::Result<Option<Vec<u8>>>,
+{
+  let mut toc = Toc::read(input.toc)?;
+  let page_counts = check_page_offsets(&toc)?;
+
+  let mut soi = BufReader::new(File::open(input.soi)?);
+  let page_sizes = read_page_sizes(&mut soi)?;
+  if (page_sizes.uncached.len(), page_sizes.cached.len()) != page_counts {
+    return Err(
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+          "the soi has {} uncached and {} cached pages, but the toc {} and {} zlib chunks",
+          page_sizes.uncached.len(),
+          page_sizes.cached.len(),
+          page_counts.0,
+          page_counts.1
+        ),
+      )
+      .into(),
+    );
+  }
+
+  // every chunk but the last of a section is full
+  let chunk_size = page_sizes
+    .uncached
+    .iter()
+    .chain(&page_sizes.cached)
+    .filter(|size| **size > 0)
+    .max()
+    .map_or(DEFAULT_CHUNK_SIZE, |size| *size as usize);
+
+  let mut str = Str::read(input.str)?;
+  let mut str_writer = StrWriter::create(output.str, chunk_size)?;
+
+  let mut pages = SectionPages::default();
+  let mut replaced = 0;
+  for (section_id, section) in toc.sections.iter_mut().enumerate() {
+    let mut data = str.read_section_data(section)?;
+    for component in data.uncached.iter_mut().chain(&mut data.cached) {
+      if let Some(replacement) = replace(section_id as u32, component)? {
+        component.data = replacement;
+        replaced += 1;
+      }
+    }
+
+    section.header.uncached_page_offset = pages.uncached.len() as i32;
+    section.header.cached_page_offset = pages.cached.len() as i32;
+
+    let section_pages = str_writer.write_section_data(section, &data)?;
+    pages.uncached.extend(section_pages.uncached);
+    pages.cached.extend(section_pages.cached);
+  }
+
+  toc.write(output.toc)?;
+
+  let mut soi_writer = File::create(output.soi)?;
+  rewrite_pages(&mut soi, &mut soi_writer, &pages)?;
+
+  Ok(replaced)
+}
+
+/// Checks that the page offset of every section counts the zlib chunks of the sections before it,
+/// and returns the number of uncached and cached chunks.
+fn check_page_offsets(toc: &Toc) -> BinResult<(usize, usize)> {
+  let mut uncached = 0;
+  let mut cached = 0;
+  for (section_id, section) in toc.sections.iter().enumerate() {
+    let header = &section.header;
+    if header.uncached_page_offset != uncached || header.cached_page_offset != cached {
+      return Err(
+        io::Error::new(
+          io::ErrorKind::InvalidData,
+          format!(
+            "section {section_id} starts at pages {} and {} instead of {uncached} and {cached}",
+            header.uncached_page_offset, header.cached_page_offset
+          ),
+        )
+        .into(),
+      );
+    }
+
+    uncached += header.zlib_header.uncached_amount;
+    cached += header.zlib_header.cached_amount;
+  }
+
+  Ok((uncached as usize, cached as usize))
+}
@@ -1,10 +1,14 @@
 use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
-use binrw::{BinRead, BinReaderExt, BinResult};
+use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt, Endian};
 
-#[derive(BinRead, PartialEq, Debug)]
-#[br(repr = i32)]
+use crate::utils::clean_path;
+use crate::{ComponentKind, SectionPages};
+
+#[derive(BinRead, BinWrite, PartialEq, Debug)]
+#[brw(repr = i32)]
 enum StreamingMode {
   Unknown = -1,
   _1D,
@@ -12,7 +16,7 @@ enum StreamingMode {
   Manual,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 struct Header {
   version: i32,
 
@@ -36,8 +40,9 @@ struct Header {
   reserved: [u8; 16],
 }
 
+/// Placement of a streamed object, which the entries of every section start with.
 #[derive(BinRead, Debug)]
-struct ModelInfo {
+pub struct ModelInfo {
   flags: i32,
   position: [f32; 4],
   look_vector: [f32; 4],
@@ -53,13 +58,45 @@ struct ModelInfo {
 }
 
 #[derive(BinRead, Debug)]
-struct StreamingTexture<TH: BinRead<Args<'static> = ()>> {
+pub struct StreamingTexture<TH: BinRead<Args<'static> = ()>> {
   model_info: ModelInfo,
   // might be something, currently only padding
   padding: u32,
   header: TH,
 }
 
+/// A texture that is loaded with its section instead of being streamed, assumed to be laid out
+/// like a [`StreamingTexture`].
+#[derive(BinRead, Debug)]
+pub struct StaticTexture<TH: BinRead<Args<'static> = ()>> {
+  model_info: ModelInfo,
+  padding: u32,
+  header: TH,
+}
+
+/// Assumed to be a bare [`ModelInfo`], like [`RenderableModel`] and [`CollisionModel`].
+#[derive(BinRead, Debug)]
+pub struct MotionPack {
+  model_info: ModelInfo,
+}
+
+#[derive(BinRead, Debug)]
+pub struct RenderableModel {
+  model_info: ModelInfo,
+}
+
+#[derive(BinRead, Debug)]
+pub struct CollisionModel {
+  model_info: ModelInfo,
+}
+
+/// The placement of the components of a package.
+///
+/// Only the streaming textures are known to be read right. The layouts of the other sections are
+/// assumed and haven't been checked against a shipped soi, so they are read without moving the
+/// reader, and a section that doesn't read as placements of this package is listed by
+/// [`Soi::unread_sections`] instead of failing the whole soi. Collision grids aren't read, as their
+/// layout isn't known, and are always listed there when the soi has them.
 #[derive(BinRead, Debug)]
 pub struct Soi<TH: BinRead<Args<'static> = ()> + 'static> {
   header: Header,
@@ -70,13 +107,138 @@ pub struct Soi<TH: BinRead<Args<'static> = ()> + 'static> {
   #[br(count = header.cached_pages)]
   cached_page_sizes: Vec<i32>,
 
+  #[br(seek_before = section_start(header.textures_offset))]
   #[br(count = header.streaming_textures)]
   streaming_textures: Vec<StreamingTexture<TH>>,
-  // #[br(count = header.static_textures)]
-  // static_textures: Vec<StaticTexture>,
 
-  // #[br(count = header.motion_packs)]
-  // motion_packs: Vec<MotionPack>,
+  // assumed to follow the streaming textures
+  #[br(restore_position)]
+  #[br(parse_with = parse_assumed, args(header.static_textures, header.sections))]
+  static_textures: Result<Vec<StaticTexture<TH>>, String>,
+
+  #[br(restore_position, seek_before = section_start(header.motion_packs_offset))]
+  #[br(parse_with = parse_assumed, args(header.motion_packs, header.sections))]
+  motion_packs: Result<Vec<MotionPack>, String>,
+
+  #[br(restore_position, seek_before = section_start(header.renderable_models_offset))]
+  #[br(parse_with = parse_assumed, args(header.renderable_models, header.sections))]
+  renderable_models: Result<Vec<RenderableModel>, String>,
+
+  #[br(restore_position, seek_before = section_start(header.collision_models_offset))]
+  #[br(parse_with = parse_assumed, args(header.collision_models, header.sections))]
+  collision_models: Result<Vec<CollisionModel>, String>,
+}
+
+/// Sections are stored at offsets from the start of the file, the ones without one at 0 or -1
+/// are read where the reader is.
+fn section_start(offset: i32) -> SeekFrom {
+  if offset > 0 {
+    SeekFrom::Start(offset as u64)
+  } else {
+    SeekFrom::Current(0)
+  }
+}
+
+/// Entries of a section that start with a [`ModelInfo`].
+trait Placed {
+  fn placement(&self) -> &ModelInfo;
+}
+
+/// Reads `count` entries of a section whose layout is assumed, or why not if one of them doesn't
+/// read or isn't a placement in one of the `sections` of the package, which a wrong layout or
+/// offset reads as.
+fn parse_assumed<R, T>(
+  reader: &mut R,
+  endian: Endian,
+  (count, sections): (i32, i32),
+) -> BinResult<Result<Vec<T>, String>>
+where
+  R: Read + Seek,
+  T: BinRead<Args<'static> = ()> + Placed,
+{
+  let mut entries = Vec::new();
+  for index in 0..count {
+    let entry = match T::read_options(reader, endian, ()) {
+      Ok(entry) => entry,
+      Err(err) => return Ok(Err(format!("entry {index} doesn't read: {err}"))),
+    };
+
+    let model_info = entry.placement();
+    if !(0..sections).contains(&model_info.section_id)
+      || model_info.component_id < 0
+      || !matches!(model_info.is_animated, 0 | 1)
+      || !model_info.name.contains(&0)
+    {
+      return Ok(Err(format!(
+        "entry {index} isn't a placement in this package (section {}, component {})",
+        model_info.section_id, model_info.component_id
+      )));
+    }
+
+    entries.push(entry);
+  }
+
+  Ok(Ok(entries))
+}
+
+/// A section of a soi that isn't available, with the reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnreadSection {
+  pub section: &'static str,
+  pub reason: String,
+}
+
+/// Decompressed size of zlib chunks, for packages without pages to tell it.
+pub const DEFAULT_CHUNK_SIZE: usize = 0x10000;
+
+/// Reads the page sizes of a soi, which are the sizes the zlib chunks of the package decompress to.
+pub(crate) fn read_page_sizes<R: Read + Seek>(read: &mut R) -> BinResult<SectionPages> {
+  let header: Header = read.read_be()?;
+  let mut page_sizes = |count: i32| (0..count).map(|_| read.read_be()).collect::<BinResult<_>>();
+
+  Ok(SectionPages {
+    uncached: page_sizes(header.uncached_pages)?,
+    cached: page_sizes(header.cached_pages)?,
+  })
+}
+
+/// Copies the soi of `read` with the page sizes of `pages`, moving the sections that follow them.
+pub(crate) fn rewrite_pages<R: Read + Seek, W: Write + Seek>(
+  read: &mut R,
+  write: &mut W,
+  pages: &SectionPages,
+) -> BinResult<()> {
+  read.seek(SeekFrom::Start(0))?;
+  let mut header: Header = read.read_be()?;
+
+  let pages_start = read.stream_position()?;
+  let pages_end = pages_start + 4 * (header.uncached_pages + header.cached_pages) as u64;
+  read.seek(SeekFrom::Start(pages_end))?;
+  let mut rest = Vec::new();
+  read.read_to_end(&mut rest)?;
+
+  let moved_by =
+    4 * (pages.uncached.len() + pages.cached.len()) as i64 - (pages_end - pages_start) as i64;
+  for offset in [
+    &mut header.motion_packs_offset,
+    &mut header.renderable_models_offset,
+    &mut header.collision_models_offset,
+    &mut header.textures_offset,
+    &mut header.collision_grids_offset,
+  ] {
+    if *offset > 0 && *offset as u64 >= pages_end {
+      *offset = (*offset as i64 + moved_by) as i32;
+    }
+  }
+  header.uncached_pages = pages.uncached.len() as i32;
+  header.cached_pages = pages.cached.len() as i32;
+
+  write.write_be(&header)?;
+  write.write_be(&pages.uncached)?;
+  write.write_be(&pages.cached)?;
+  write.write_all(&rest)?;
+
+  Ok(())
 }
 
 impl<TH: BinRead<Args<'static> = ()>> Soi<TH> {
@@ -90,15 +252,269 @@ impl<TH: BinRead<Args<'static> = ()>> Soi<TH> {
   }
 
   pub fn find_texture_header(&self, section_id: u32, component_id: u32) -> Option<&TH> {
-    for texture in &self.streaming_textures {
-      let model_info = &texture.model_info;
-      if model_info.section_id == section_id as i32
-        && model_info.component_id == component_id as i32
-      {
-        return Some(&texture.header);
+    let streaming = self
+      .streaming_textures
+      .iter()
+      .map(|texture| (&texture.model_info, &texture.header));
+    let r#static = self
+      .static_textures
+      .iter()
+      .flatten()
+      .map(|texture| (&texture.model_info, &texture.header));
+
+    streaming
+      .chain(r#static)
+      .find(|(model_info, _)| model_info.is(section_id, component_id))
+      .map(|(_, header)| header)
+  }
+
+  /// The placement of a component, from the section of its kind.
+  pub fn find_model_info(
+    &self,
+    kind: ComponentKind,
+    section_id: u32,
+    component_id: u32,
+  ) -> Option<&ModelInfo> {
+    let mut model_infos: Box<dyn Iterator<Item = &ModelInfo>> = match kind {
+      ComponentKind::Texture => Box::new(
+        self
+          .streaming_textures
+          .iter()
+          .map(StreamingTexture::model_info)
+          .chain(
+            self
+              .static_textures
+              .iter()
+              .flatten()
+              .map(StaticTexture::model_info),
+          ),
+      ),
+      ComponentKind::RenderableModel => Box::new(
+        self
+          .renderable_models
+          .iter()
+          .flatten()
+          .map(Placed::placement),
+      ),
+      ComponentKind::CollisionModel => Box::new(
+        self
+          .collision_models
+          .iter()
+          .flatten()
+          .map(Placed::placement),
+      ),
+      ComponentKind::MotionPack => {
+        Box::new(self.motion_packs.iter().flatten().map(Placed::placement))
       }
+      ComponentKind::UserData | ComponentKind::CollisionGrid => return None,
+    };
+
+    model_infos.find(|model_info| model_info.is(section_id, component_id))
+  }
+
+  /// Every placed object with the kind of its component, textures first, followed by motion packs,
+  /// renderable models and collision models.
+  pub fn model_infos(&self) -> impl Iterator<Item = (ComponentKind, &ModelInfo)> {
+    let textures = self
+      .streaming_textures
+      .iter()
+      .map(StreamingTexture::model_info)
+      .chain(
+        self
+          .static_textures
+          .iter()
+          .flatten()
+          .map(StaticTexture::model_info),
+      )
+      .map(|model_info| (ComponentKind::Texture, model_info));
+    let motion_packs = self
+      .motion_packs
+      .iter()
+      .flatten()
+      .map(|pack| (ComponentKind::MotionPack, &pack.model_info));
+    let renderable_models = self
+      .renderable_models
+      .iter()
+      .flatten()
+      .map(|model| (ComponentKind::RenderableModel, &model.model_info));
+    let collision_models = self
+      .collision_models
+      .iter()
+      .flatten()
+      .map(|model| (ComponentKind::CollisionModel, &model.model_info));
+
+    textures
+      .chain(motion_packs)
+      .chain(renderable_models)
+      .chain(collision_models)
+  }
+
+  pub fn streaming_textures(&self) -> &[StreamingTexture<TH>] {
+    &self.streaming_textures
+  }
+
+  /// `None` if the section didn't read as expected, see [`Soi::unread_sections`].
+  pub fn static_textures(&self) -> Option<&[StaticTexture<TH>]> {
+    self.static_textures.as_deref().ok()
+  }
+
+  /// `None` if the section didn't read as expected, see [`Soi::unread_sections`].
+  pub fn motion_packs(&self) -> Option<&[MotionPack]> {
+    self.motion_packs.as_deref().ok()
+  }
+
+  /// `None` if the section didn't read as expected, see [`Soi::unread_sections`].
+  pub fn renderable_models(&self) -> Option<&[RenderableModel]> {
+    self.renderable_models.as_deref().ok()
+  }
+
+  /// `None` if the section didn't read as expected, see [`Soi::unread_sections`].
+  pub fn collision_models(&self) -> Option<&[CollisionModel]> {
+    self.collision_models.as_deref().ok()
+  }
+
+  /// The sections that aren't available, in the order they are stored in the struct.
+  pub fn unread_sections(&self) -> Vec<UnreadSection> {
+    let mut unread: Vec<_> = [
+      ("static textures", self.static_textures.as_ref().err()),
+      ("motion packs", self.motion_packs.as_ref().err()),
+      ("renderable models", self.renderable_models.as_ref().err()),
+      ("collision models", self.collision_models.as_ref().err()),
+    ]
+    .into_iter()
+    .filter_map(|(section, reason)| {
+      reason.map(|reason| UnreadSection {
+        section,
+        reason: reason.clone(),
+      })
+    })
+    .collect();
+
+    if self.header.collision_grids_offset > 0 {
+      unread.push(UnreadSection {
+        section: "collision grids",
+        reason: "their layout isn't known".to_string(),
+      });
     }
 
-    None
+    unread
+  }
+
+  pub fn uncached_page_sizes(&self) -> &[i32] {
+    &self.uncached_page_sizes
+  }
+
+  pub fn cached_page_sizes(&self) -> &[i32] {
+    &self.cached_page_sizes
+  }
+}
+
+impl ModelInfo {
+  fn is(&self, section_id: u32, component_id: u32) -> bool {
+    self.section_id == section_id as i32 && self.component_id == component_id as i32
+  }
+
+  pub fn flags(&self) -> i32 {
+    self.flags
+  }
+
+  /// Homogeneous coordinates, with w = 1.
+  pub fn position(&self) -> [f32; 4] {
+    self.position
+  }
+
+  /// The direction the object faces.
+  pub fn look_vector(&self) -> [f32; 4] {
+    self.look_vector
+  }
+
+  pub fn up_vector(&self) -> [f32; 4] {
+    self.up_vector
+  }
+
+  pub fn is_animated(&self) -> bool {
+    self.is_animated != 0
+  }
+
+  pub fn section_id(&self) -> u32 {
+    self.section_id as u32
+  }
+
+  pub fn component_id(&self) -> u32 {
+    self.component_id as u32
+  }
+
+  pub fn name(&self) -> String {
+    clean_path(&self.name)
+  }
+
+  pub fn zone(&self) -> i32 {
+    self.zone
+  }
+
+  pub fn parameter_count(&self) -> i32 {
+    self.parameter_count
+  }
+}
+
+impl<TH: BinRead<Args<'static> = ()>> StreamingTexture<TH> {
+  pub fn model_info(&self) -> &ModelInfo {
+    &self.model_info
+  }
+
+  pub fn header(&self) -> &TH {
+    &self.header
+  }
+}
+
+impl<TH: BinRead<Args<'static> = ()>> StaticTexture<TH> {
+  pub fn model_info(&self) -> &ModelInfo {
+    &self.model_info
+  }
+
+  pub fn header(&self) -> &TH {
+    &self.header
+  }
+}
+
+impl<TH: BinRead<Args<'static> = ()>> Placed for StaticTexture<TH> {
+  fn placement(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl Placed for MotionPack {
+  fn placement(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl Placed for RenderableModel {
+  fn placement(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl Placed for CollisionModel {
+  fn placement(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl MotionPack {
+  pub fn model_info(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl RenderableModel {
+  pub fn model_info(&self) -> &ModelInfo {
+    &self.model_info
+  }
+}
+
+impl CollisionModel {
+  pub fn model_info(&self) -> &ModelInfo {
+    &self.model_info
   }
 }
@@ -2,7 +2,7 @@ use std::path::Path;
 
 use binrw::{BinRead, BinResult};
 
-use crate::{ComponentHeader, Section, Soi, Toc};
+use crate::{ComponentHeader, ComponentKind, ModelInfo, Section, Soi, Toc};
 
 pub struct SoiSoup<TH: BinRead<Args<'static> = ()> + 'static> {
   toc: Toc,
@@ -62,4 +62,23 @@ impl<TH: BinRead<Args<'static> = ()>> SoiSoup<TH> {
     let (section_id, component_id) = self.toc.find_ids(instance_id)?;
     self.soi.find_texture_header(section_id, component_id)
   }
+
+  pub fn find_model_info(
+    &self,
+    kind: ComponentKind,
+    section_id: u32,
+    component_id: u32,
+    instance_id: u32,
+  ) -> Option<&ModelInfo> {
+    if let Some(model_info) = self.soi.find_model_info(kind, section_id, component_id) {
+      return Some(model_info);
+    }
+
+    let (section_id, component_id) = self.toc.find_ids(instance_id)?;
+    self.soi.find_model_info(kind, section_id, component_id)
+  }
+
+  pub fn soi(&self) -> &Soi<TH> {
+    &self.soi
+  }
 }
@@ -1,13 +1,18 @@
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use binrw::io;
 use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 
-use crate::toc::ComponentKind;
+use crate::toc::{ComponentKind, MemoryEntry};
 use crate::{ComponentHeader, Section};
 
+/// Largest alignment [`StrWriter`] keeps when it moves sections and components.
+const MAX_ALIGNMENT: u64 = 0x1000;
+
 #[derive(Debug)]
 pub struct SectionData {
   pub uncached: Vec<ComponentData>,
@@ -79,6 +84,152 @@ impl Str {
   }
 }
 
+/// Sizes the zlib chunks of sections decompress to, which the soi lists as their pages.
+#[derive(Debug, Default)]
+pub struct SectionPages {
+  pub uncached: Vec<i32>,
+  pub cached: Vec<i32>,
+}
+
+/// Data and zlib chunks of the uncached or cached components of a section.
+struct Chunks {
+  data_size: usize,
+  pages: Vec<i32>,
+  compressed_sizes: Vec<i32>,
+}
+
+/// Writes sections to a new str, see [`StrWriter::write_section_data`].
+pub struct StrWriter<W: Write + Seek> {
+  writer: W,
+  chunk_size: usize,
+}
+
+impl StrWriter<File> {
+  pub fn create(path: &Path, chunk_size: usize) -> io::Result<Self> {
+    Ok(Self::new(File::create(path)?, chunk_size))
+  }
+}
+
+impl<W: Write + Seek> StrWriter<W> {
+  /// Splits the data of every section into zlib chunks that decompress to `chunk_size` bytes, see
+  /// [`crate::Toc::chunk_size`].
+  pub fn new(writer: W, chunk_size: usize) -> Self {
+    Self { writer, chunk_size }
+  }
+
+  /// Compresses `data` at the end of the str and points `section` and its components at it.
+  ///
+  /// The components of `data` must be those of `section` in the same order, like
+  /// [`Str::read_section_data`] returns them. Sections and components are laid out in the order of
+  /// their previous offsets, and keep their alignment up to 4 KiB, as textures have to be aligned.
+  pub fn write_section_data(
+    &mut self,
+    section: &mut Section,
+    data: &SectionData,
+  ) -> io::Result<SectionPages> {
+    let alignment = alignment(section.header.memory_entry.offset);
+    let start = self.writer.stream_position()?;
+    let offset = start.next_multiple_of(alignment);
+    self.writer.write_all(&vec![0; (offset - start) as usize])?;
+
+    let uncached = self.write_components(&mut section.uncached_components, &data.uncached)?;
+    let cached = self.write_components(&mut section.cached_components, &data.cached)?;
+    let end = self.writer.stream_position()?;
+
+    let header = &mut section.header;
+    header.memory_entry = MemoryEntry {
+      offset: offset as i32,
+      size: (end - offset) as i32,
+    };
+    header.uncached_data_size = uncached.data_size as i32;
+    header.cached_data_size = cached.data_size as i32;
+
+    let zlib = &mut header.zlib_header;
+    zlib.uncached_total_size = uncached.compressed_sizes.iter().sum();
+    zlib.cached_total_size = cached.compressed_sizes.iter().sum();
+    zlib.uncached_amount = uncached.compressed_sizes.len() as i32;
+    zlib.cached_amount = cached.compressed_sizes.len() as i32;
+    zlib.uncached_sizes = uncached.compressed_sizes;
+    zlib.cached_sizes = cached.compressed_sizes;
+
+    Ok(SectionPages {
+      uncached: uncached.pages,
+      cached: cached.pages,
+    })
+  }
+
+  fn write_components(
+    &mut self,
+    headers: &mut [ComponentHeader],
+    components: &[ComponentData],
+  ) -> io::Result<Chunks> {
+    if headers.len() != components.len() {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+          "the section has {} components, but data for {} was given",
+          headers.len(),
+          components.len()
+        ),
+      ));
+    }
+
+    let mut order: Vec<_> = (0..headers.len()).collect();
+    order.sort_by_key(|index| headers[*index].memory_entry.offset);
+
+    let mut data = Vec::new();
+    for index in order {
+      let header = &mut headers[index];
+      let component = &components[index];
+      if component.id != header.id as u32 {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!(
+            "expected data for component {}, but got it for {}",
+            header.id, component.id
+          ),
+        ));
+      }
+
+      let alignment = alignment(header.memory_entry.offset);
+      data.resize((data.len() as u64).next_multiple_of(alignment) as usize, 0);
+
+      header.memory_entry = MemoryEntry {
+        offset: data.len() as i32,
+        size: component.data.len() as i32,
+      };
+      data.extend_from_slice(&component.data);
+    }
+
+    let mut pages = Vec::new();
+    let mut compressed_sizes = Vec::new();
+    for chunk in data.chunks(self.chunk_size) {
+      let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+      encoder.write_all(chunk)?;
+      let compressed = encoder.finish()?;
+      self.writer.write_all(&compressed)?;
+
+      pages.push(chunk.len() as i32);
+      compressed_sizes.push(compressed.len() as i32);
+    }
+
+    Ok(Chunks {
+      data_size: data.len(),
+      pages,
+      compressed_sizes,
+    })
+  }
+}
+
+/// The largest power of two `offset` is a multiple of, up to [`MAX_ALIGNMENT`].
+fn alignment(offset: i32) -> u64 {
+  if offset <= 0 {
+    return MAX_ALIGNMENT;
+  }
+
+  (1 << offset.trailing_zeros()).min(MAX_ALIGNMENT)
+}
+
 fn extract_components(headers: &[ComponentHeader], data: Vec<u8>) -> Vec<ComponentData> {
   let mut components = Vec::with_capacity(headers.len());
 
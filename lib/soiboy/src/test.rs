@@ -1,10 +1,17 @@
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, File};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
+use binrw::BinReaderExt;
+
 use x_flipper_360::{Config, Format, TextureFormat, TextureHeader, TextureSize2D};
 
+use crate::toc::{Bounding, MemoryEntry, ZlibHeader};
 use crate::ComponentKind::Texture;
-use crate::{ComponentData, SoiSoup, Str};
+use crate::{
+  repack, ComponentData, ComponentHeader, ComponentKind, PackagePaths, Section, SectionData,
+  SectionHeader, Soi, SoiSoup, Str, StrWriter, Toc, UnreadSection,
+};
 
 #[test]
 fn extract() {
@@ -28,6 +35,238 @@ fn extract() {
   }
 }
 
+fn model_info(section_id: i32, component_id: i32, name: &str) -> Vec<u8> {
+  let mut data = Vec::new();
+  data.extend(0i32.to_be_bytes()); // flags
+  data.extend([1.0f32, 2.0, 3.0, 1.0].iter().flat_map(|f| f.to_be_bytes()));
+  data.extend([0.0f32, 0.0, 1.0, 0.0].iter().flat_map(|f| f.to_be_bytes()));
+  data.extend([0.0f32, 1.0, 0.0, 0.0].iter().flat_map(|f| f.to_be_bytes()));
+  for value in [0, section_id, component_id] {
+    data.extend(value.to_be_bytes());
+  }
+
+  let mut raw_name = [0u8; 260];
+  raw_name[..name.len()].copy_from_slice(name.as_bytes());
+  data.extend(raw_name);
+
+  data.extend(7i32.to_be_bytes()); // zone
+  data.extend(0i32.to_be_bytes()); // parameter count
+  data
+}
+
+#[test]
+fn soi_sections() {
+  const HEADER_SIZE: i32 = 80;
+  const MODEL_INFO_SIZE: i32 = 332;
+  const TEXTURE_SIZE: i32 = MODEL_INFO_SIZE + 8;
+
+  let renderable_models_offset = HEADER_SIZE + 4 + 2 * TEXTURE_SIZE;
+  let collision_grids_offset = renderable_models_offset + MODEL_INFO_SIZE;
+
+  let mut data = Vec::new();
+  let header = [
+    1, // version
+    0, // flags
+    1, // sections
+    0, // collision models
+    1, // renderable models
+    0, // motion packs
+    1, // streaming textures
+    1, // static textures
+    1, // uncached pages
+    0, // cached pages
+    -1,
+    renderable_models_offset,
+    -1,
+    HEADER_SIZE + 4,
+    collision_grids_offset,
+    2, // manual streaming
+  ];
+  data.extend(header.iter().flat_map(|value| value.to_be_bytes()));
+  data.extend([0; 16]);
+  data.extend(0x1000i32.to_be_bytes());
+
+  for (component_id, texture_header) in [(1, 0xaau32), (2, 0xbb)] {
+    data.extend(model_info(0, component_id, "texture"));
+    data.extend(0u32.to_be_bytes());
+    data.extend(texture_header.to_be_bytes());
+  }
+  data.extend(model_info(0, 3, "model"));
+  data.extend([1, 2, 3, 4]);
+
+  let soi: Soi<u32> = Cursor::new(&data).read_be().unwrap();
+  assert_eq!(soi.streaming_textures().len(), 1);
+  assert_eq!(soi.find_texture_header(0, 2), Some(&0xbb));
+  assert_eq!(soi.renderable_models().unwrap().len(), 1);
+  assert!(soi.motion_packs().unwrap().is_empty());
+  assert!(soi.collision_models().unwrap().is_empty());
+
+  let model = soi
+    .find_model_info(ComponentKind::RenderableModel, 0, 3)
+    .unwrap();
+  assert_eq!(model.name(), "model");
+  assert_eq!(model.position(), [1.0, 2.0, 3.0, 1.0]);
+  assert_eq!(model.look_vector(), [0.0, 0.0, 1.0, 0.0]);
+  assert_eq!((model.section_id(), model.component_id()), (0, 3));
+  assert_eq!(model.zone(), 7);
+  assert!(!model.is_animated());
+  assert_eq!(soi.model_infos().count(), 3);
+  assert!(soi.find_model_info(ComponentKind::Texture, 0, 3).is_none());
+  assert_eq!(
+    soi.unread_sections(),
+    [UnreadSection {
+      section: "collision grids",
+      reason: "their layout isn't known".to_string(),
+    }]
+  );
+
+  // renderable models pointing at the collision grids run out of data and are reported
+  let mut wrong_offset = data;
+  wrong_offset[11 * 4..12 * 4].copy_from_slice(&collision_grids_offset.to_be_bytes());
+  let soi: Soi<u32> = Cursor::new(wrong_offset).read_be().unwrap();
+  assert!(soi.renderable_models().is_none());
+  assert_eq!(soi.unread_sections()[0].section, "renderable models");
+  assert!(soi.unread_sections()[0]
+    .reason
+    .starts_with("entry 0 doesn't read"));
+  assert_eq!(soi.static_textures().unwrap().len(), 1);
+  assert_eq!(soi.find_texture_header(0, 1), Some(&0xaa));
+}
+
+fn component(id: i32, path: &str, offset: i32, kind: ComponentKind) -> ComponentHeader {
+  let mut raw_path = [0u8; 260];
+  raw_path[..path.len()].copy_from_slice(path.as_bytes());
+
+  ComponentHeader {
+    raw_path,
+    instance_id: 100 + id,
+    id,
+    memory_entry: MemoryEntry { offset, size: 0 },
+    kind,
+  }
+}
+
+#[test]
+fn repack_package() {
+  let dir = std::env::temp_dir().join(format!("soiboy-repack-{}", std::process::id()));
+  create_dir_all(&dir).unwrap();
+  let input = PackagePaths {
+    toc: &dir.join("in.toc"),
+    soi: &dir.join("in.soi"),
+    str: &dir.join("in.str"),
+  };
+  let output = PackagePaths {
+    toc: &dir.join("out.toc"),
+    soi: &dir.join("out.soi"),
+    str: &dir.join("out.str"),
+  };
+
+  let mut section = Section {
+    header: SectionHeader {
+      name: [0; 260],
+      total_component_count: 2,
+      uncached_component_count: 2,
+      cached_component_count: 0,
+      shared_section_offset: 0,
+      uncached_page_offset: 0,
+      cached_page_offset: 0,
+      link_table: [0; 8],
+      bounding: Bounding {
+        min_x: 0.0,
+        max_x: 0.0,
+        min_y: 0.0,
+        max_y: 0.0,
+        min_z: 0.0,
+        max_z: 0.0,
+      },
+      memory_entry: MemoryEntry { offset: 0, size: 0 },
+      uncached_data_size: 0,
+      cached_data_size: 0,
+      zlib_header: ZlibHeader {
+        uncached_total_size: 0,
+        cached_total_size: 0,
+        uncached_amount: 0,
+        cached_amount: 0,
+        uncached_sizes: vec![],
+        cached_sizes: vec![],
+      },
+    },
+    uncached_components: vec![
+      component(1, "textures\\a", 0, ComponentKind::Texture),
+      component(2, "b", 16, ComponentKind::UserData),
+    ],
+    cached_components: vec![],
+  };
+
+  let data = SectionData {
+    uncached: vec![
+      ComponentData {
+        id: 1,
+        path: "textures/a".to_string(),
+        instance_id: 101,
+        kind: ComponentKind::Texture,
+        data: vec![1; 8],
+      },
+      ComponentData {
+        id: 2,
+        path: "b".to_string(),
+        instance_id: 102,
+        kind: ComponentKind::UserData,
+        data: vec![2; 4],
+      },
+    ],
+    cached: vec![],
+  };
+
+  // component 2 stays aligned to 16 bytes, so the data is split into pages of 16 and 4 bytes
+  let mut str_writer = StrWriter::create(input.str, 16).unwrap();
+  let pages = str_writer.write_section_data(&mut section, &data).unwrap();
+  assert_eq!(pages.uncached, [16, 4]);
+  drop(str_writer);
+
+  Toc {
+    sections: vec![section],
+  }
+  .write(input.toc)
+  .unwrap();
+
+  let mut soi = Vec::new();
+  // two uncached pages, followed by the collision grids at 80 + 2 * 4
+  let header: [i32; 16] = [1, 0, 1, 0, 0, 0, 0, 0, 2, 0, -1, -1, -1, -1, 88, 2];
+  soi.extend(header.iter().flat_map(|value| value.to_be_bytes()));
+  soi.extend([0; 16]);
+  soi.extend([16i32, 4].iter().flat_map(|value| value.to_be_bytes()));
+  soi.extend([9; 4]);
+  fs::write(input.soi, soi).unwrap();
+
+  let replaced = repack(&input, &output, |section_id, component| {
+    Ok((section_id == 0 && component.path == "b").then(|| vec![3; 20]))
+  })
+  .unwrap();
+  assert_eq!(replaced, 1);
+
+  let toc = Toc::read(output.toc).unwrap();
+  let section = &toc.sections[0];
+  let data = Str::read(output.str)
+    .unwrap()
+    .read_section_data(section)
+    .unwrap();
+  assert_eq!(data.uncached[0].data, [1; 8]);
+  assert_eq!(data.uncached[1].data, [3; 20]);
+  assert_eq!(section.uncached_components[1].memory_entry.offset, 16);
+  assert_eq!(section.header.zlib_header.uncached_amount, 3);
+
+  let soi = Soi::<u32>::read(output.soi).unwrap();
+  assert_eq!(soi.uncached_page_sizes(), [16, 16, 4]);
+
+  // the collision grids moved by the added page
+  let soi = fs::read(output.soi).unwrap();
+  assert_eq!(soi[14 * 4..15 * 4], 92i32.to_be_bytes());
+  assert!(soi.ends_with(&[9; 4]));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
 fn process_component(soup: &SoiSoup<TextureHeader>, section_id: u32, component: ComponentData) {
   if component.kind != Texture {
     return;
@@ -1,12 +1,12 @@
 use std::fs::File;
-use std::io::Seek;
+use std::io::{Seek, Write};
 use std::path::Path;
 
-use binrw::{BinRead, BinReaderExt, BinResult};
+use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, BinWriterExt};
 
 use crate::utils::clean_path;
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub(crate) struct Bounding {
   pub(crate) min_x: f32,
   pub(crate) max_x: f32,
@@ -18,14 +18,14 @@ pub(crate) struct Bounding {
   pub(crate) max_z: f32,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub(crate) struct MemoryEntry {
   pub(crate) offset: i32,
   pub(crate) size: i32,
 }
 
-#[derive(BinRead, PartialEq, Copy, Clone, Debug)]
-#[br(repr = i32)]
+#[derive(BinRead, BinWrite, PartialEq, Copy, Clone, Debug)]
+#[brw(repr = i32)]
 pub enum ComponentKind {
   RenderableModel,
   Texture,
@@ -35,7 +35,7 @@ pub enum ComponentKind {
   CollisionGrid,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub(crate) struct ZlibHeader {
   pub(crate) uncached_total_size: i32,
   pub(crate) cached_total_size: i32,
@@ -50,7 +50,7 @@ pub(crate) struct ZlibHeader {
   pub(crate) cached_sizes: Vec<i32>,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub struct SectionHeader {
   pub name: [u8; 260],
 
@@ -73,9 +73,9 @@ pub struct SectionHeader {
   pub(crate) zlib_header: ZlibHeader,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub struct ComponentHeader {
-  raw_path: [u8; 260],
+  pub(crate) raw_path: [u8; 260],
 
   pub instance_id: i32,
   pub id: i32,
@@ -83,7 +83,7 @@ pub struct ComponentHeader {
   pub kind: ComponentKind,
 }
 
-#[derive(BinRead, Debug)]
+#[derive(BinRead, BinWrite, Debug)]
 pub struct Section {
   pub header: SectionHeader,
 
@@ -118,6 +118,19 @@ impl Toc {
     Ok(Self { sections })
   }
 
+  pub fn write(&self, path: &Path) -> BinResult<()> {
+    let mut file = File::create(path)?;
+    self.write_file(&mut file)
+  }
+
+  pub fn write_file<W: Write + Seek>(&self, writer: &mut W) -> BinResult<()> {
+    for section in &self.sections {
+      writer.write_be(section)?;
+    }
+
+    Ok(())
+  }
+
   pub fn find_section(&self, id: u32) -> Option<&Section> {
     self.sections.get(id as usize)
   }
@@ -0,0 +1,328 @@
+use crate::format::{get_format_data, Format};
+
+// https://learn.microsoft.com/en-us/windows/win32/direct3d10/d3d10-graphics-programming-guide-resources-block-compression
+
+const BLOCK_SIZE: u32 = 4;
+const BLOCK_PIXELS: usize = (BLOCK_SIZE * BLOCK_SIZE) as usize;
+
+type Rgba = [u8; 4];
+
+/// Decodes a single mip level into 8 bit RGBA pixels, row by row.
+pub(crate) fn decompress(format: &Format, width: u32, height: u32, src: &[u8]) -> Vec<u8> {
+  let mut output = vec![0; (width * height * 4) as usize];
+
+  if let Format::RGBA8 = format {
+    for (pixel, bgra) in output.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+      pixel.copy_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+    }
+    return output;
+  }
+
+  let bytes_per_block = get_format_data(format).bytes_per_block as usize;
+  let blocks = src.chunks_exact(bytes_per_block);
+
+  for (i, block) in blocks.take(block_count(width, height)).enumerate() {
+    let pixels = match format {
+      Format::Dxt1 => decode_color_block(block, true),
+      Format::Dxt3 => {
+        let mut pixels = decode_color_block(&block[8..], false);
+        let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+        for (p, pixel) in pixels.iter_mut().enumerate() {
+          pixel[3] = ((alpha >> (4 * p)) & 0xF) as u8 * 17;
+        }
+        pixels
+      }
+      Format::Dxt5 => {
+        let mut pixels = decode_color_block(&block[8..], false);
+        let alpha = decode_alpha_block(&block[..8]);
+        for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+          pixel[3] = alpha;
+        }
+        pixels
+      }
+      Format::RGBA8 => unreachable!(),
+    };
+
+    let blocks_x = blocks_in(width) as usize;
+    let (block_x, block_y) = ((i % blocks_x) as u32, (i / blocks_x) as u32);
+
+    for (p, pixel) in pixels.iter().enumerate() {
+      let x = block_x * BLOCK_SIZE + p as u32 % BLOCK_SIZE;
+      let y = block_y * BLOCK_SIZE + p as u32 / BLOCK_SIZE;
+      if x < width && y < height {
+        let offset = ((y * width + x) * 4) as usize;
+        output[offset..offset + 4].copy_from_slice(pixel);
+      }
+    }
+  }
+
+  output
+}
+
+/// Encodes a single mip level of 8 bit RGBA pixels, row by row.
+pub(crate) fn compress(format: &Format, width: u32, height: u32, src: &[u8]) -> Vec<u8> {
+  if let Format::RGBA8 = format {
+    return src
+      .chunks_exact(4)
+      .flat_map(|rgba| [rgba[2], rgba[1], rgba[0], rgba[3]])
+      .collect();
+  }
+
+  let bytes_per_block = get_format_data(format).bytes_per_block as usize;
+  let mut output = Vec::with_capacity(block_count(width, height) * bytes_per_block);
+
+  for block_y in 0..blocks_in(height) {
+    for block_x in 0..blocks_in(width) {
+      // pixels beyond the edge repeat the last row/column
+      let mut pixels = [[0u8; 4]; BLOCK_PIXELS];
+      for (p, pixel) in pixels.iter_mut().enumerate() {
+        let x = (block_x * BLOCK_SIZE + p as u32 % BLOCK_SIZE).min(width - 1);
+        let y = (block_y * BLOCK_SIZE + p as u32 / BLOCK_SIZE).min(height - 1);
+        let offset = ((y * width + x) * 4) as usize;
+        pixel.copy_from_slice(&src[offset..offset + 4]);
+      }
+
+      match format {
+        Format::Dxt1 => output.extend_from_slice(&encode_color_block(&pixels, true)),
+        Format::Dxt3 => {
+          let mut alpha = 0u64;
+          for (p, pixel) in pixels.iter().enumerate() {
+            alpha |= (((pixel[3] as u64) + 8) / 17) << (4 * p);
+          }
+          output.extend_from_slice(&alpha.to_le_bytes());
+          output.extend_from_slice(&encode_color_block(&pixels, false));
+        }
+        Format::Dxt5 => {
+          output.extend_from_slice(&encode_alpha_block(&pixels));
+          output.extend_from_slice(&encode_color_block(&pixels, false));
+        }
+        Format::RGBA8 => unreachable!(),
+      }
+    }
+  }
+
+  output
+}
+
+/// Halves the size of an RGBA image by averaging 2x2 pixels.
+pub(crate) fn downsample(width: u32, height: u32, src: &[u8]) -> (u32, u32, Vec<u8>) {
+  let new_width = (width / 2).max(1);
+  let new_height = (height / 2).max(1);
+  let mut output = Vec::with_capacity((new_width * new_height * 4) as usize);
+
+  for y in 0..new_height {
+    for x in 0..new_width {
+      let mut sum = [0u32; 4];
+      for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let sx = (x * 2 + dx).min(width - 1);
+        let sy = (y * 2 + dy).min(height - 1);
+        let offset = ((sy * width + sx) * 4) as usize;
+        for c in 0..4 {
+          sum[c] += src[offset + c] as u32;
+        }
+      }
+      output.extend(sum.map(|c| ((c + 2) / 4) as u8));
+    }
+  }
+
+  (new_width, new_height, output)
+}
+
+fn blocks_in(size: u32) -> u32 {
+  size.div_ceil(BLOCK_SIZE).max(1)
+}
+
+fn block_count(width: u32, height: u32) -> usize {
+  (blocks_in(width) * blocks_in(height)) as usize
+}
+
+fn unpack_565(color: u16) -> Rgba {
+  let r = ((color >> 11) & 0x1F) as u8;
+  let g = ((color >> 5) & 0x3F) as u8;
+  let b = (color & 0x1F) as u8;
+  [
+    (r << 3) | (r >> 2),
+    (g << 2) | (g >> 4),
+    (b << 3) | (b >> 2),
+    255,
+  ]
+}
+
+fn pack_565(color: [f32; 3]) -> u16 {
+  let r = (color[0].clamp(0.0, 255.0) * 31.0 / 255.0).round() as u16;
+  let g = (color[1].clamp(0.0, 255.0) * 63.0 / 255.0).round() as u16;
+  let b = (color[2].clamp(0.0, 255.0) * 31.0 / 255.0).round() as u16;
+  (r << 11) | (g << 5) | b
+}
+
+fn color_palette(color0: u16, color1: u16, allow_transparency: bool) -> [Rgba; 4] {
+  let c0 = unpack_565(color0);
+  let c1 = unpack_565(color1);
+  let mix = |w0: u16, w1: u16| -> Rgba {
+    let mut color = [255; 4];
+    for c in 0..3 {
+      color[c] = ((c0[c] as u16 * w0 + c1[c] as u16 * w1) / (w0 + w1)) as u8;
+    }
+    color
+  };
+
+  if color0 > color1 || !allow_transparency {
+    [c0, c1, mix(2, 1), mix(1, 2)]
+  } else {
+    [c0, c1, mix(1, 1), [0, 0, 0, 0]]
+  }
+}
+
+fn decode_color_block(block: &[u8], allow_transparency: bool) -> [Rgba; BLOCK_PIXELS] {
+  let color0 = u16::from_le_bytes([block[0], block[1]]);
+  let color1 = u16::from_le_bytes([block[2], block[3]]);
+  let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+  let palette = color_palette(color0, color1, allow_transparency);
+  std::array::from_fn(|p| palette[((indices >> (2 * p)) & 0b11) as usize])
+}
+
+fn encode_color_block(pixels: &[Rgba; BLOCK_PIXELS], allow_transparency: bool) -> [u8; 8] {
+  let transparent = allow_transparency && pixels.iter().any(|pixel| pixel[3] < 128);
+  let opaque: Vec<_> = pixels
+    .iter()
+    .filter(|pixel| !transparent || pixel[3] >= 128)
+    .map(|pixel| [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32])
+    .collect();
+
+  let (mut color0, mut color1) = if opaque.is_empty() {
+    (0, 0)
+  } else {
+    let (min, max) = principal_extremes(&opaque);
+    (pack_565(max), pack_565(min))
+  };
+
+  // four colors require color0 > color1, three colors and transparency color0 <= color1
+  if (color0 < color1) != transparent && color0 != color1 {
+    std::mem::swap(&mut color0, &mut color1);
+  }
+  if transparent && color0 > color1 {
+    std::mem::swap(&mut color0, &mut color1);
+  }
+
+  let palette = color_palette(color0, color1, allow_transparency);
+  let mut indices = 0u32;
+  for (p, pixel) in pixels.iter().enumerate() {
+    let index = if transparent && pixel[3] < 128 {
+      3
+    } else {
+      let candidates = if color0 > color1 || !allow_transparency {
+        4
+      } else {
+        3
+      };
+      nearest(&palette[..candidates], |color| {
+        (0..3)
+          .map(|c| (color[c] as i32 - pixel[c] as i32).pow(2))
+          .sum()
+      })
+    };
+    indices |= (index as u32) << (2 * p);
+  }
+
+  let mut block = [0; 8];
+  block[..2].copy_from_slice(&color0.to_le_bytes());
+  block[2..4].copy_from_slice(&color1.to_le_bytes());
+  block[4..].copy_from_slice(&indices.to_le_bytes());
+  block
+}
+
+/// Projects the colors onto their principal axis and returns the outermost points on it.
+fn principal_extremes(colors: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+  let n = colors.len() as f32;
+  let mut mean = [0.0; 3];
+  for color in colors {
+    for c in 0..3 {
+      mean[c] += color[c] / n;
+    }
+  }
+
+  let mut covariance = [[0.0f32; 3]; 3];
+  for color in colors {
+    for i in 0..3 {
+      for j in 0..3 {
+        covariance[i][j] += (color[i] - mean[i]) * (color[j] - mean[j]);
+      }
+    }
+  }
+
+  // power iteration converges to the eigenvector with the largest eigenvalue, starting
+  // from the channel with the most variance so anti-correlated channels don't cancel out
+  let widest = (0..3)
+    .max_by(|&a, &b| covariance[a][a].total_cmp(&covariance[b][b]))
+    .unwrap();
+  let mut axis = [0.0f32; 3];
+  axis[widest] = 1.0;
+  for _ in 0..8 {
+    let next: [f32; 3] = std::array::from_fn(|i| (0..3).map(|j| covariance[i][j] * axis[j]).sum());
+    let length = next.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if length < f32::EPSILON {
+      break;
+    }
+    axis = next.map(|x| x / length);
+  }
+
+  let project = |color: &[f32; 3]| (0..3).map(|c| (color[c] - mean[c]) * axis[c]).sum::<f32>();
+  let (mut min, mut max) = (f32::MAX, f32::MIN);
+  for color in colors {
+    let t = project(color);
+    min = min.min(t);
+    max = max.max(t);
+  }
+
+  let point = |t: f32| std::array::from_fn(|c| mean[c] + axis[c] * t);
+  (point(min), point(max))
+}
+
+fn alpha_palette(alpha0: u8, alpha1: u8) -> [u8; 8] {
+  let (a0, a1) = (alpha0 as u16, alpha1 as u16);
+  let mut palette = [alpha0, alpha1, 0, 0, 0, 0, 0, 255];
+
+  // the first two entries are the endpoints, the rest are interpolated between them
+  let (steps, interpolated) = if alpha0 > alpha1 { (7, 6) } else { (5, 4) };
+  for (i, alpha) in palette.iter_mut().enumerate().skip(2).take(interpolated) {
+    let i = i as u16 - 1;
+    *alpha = (((steps - i) * a0 + i * a1) / steps) as u8;
+  }
+
+  palette
+}
+
+fn decode_alpha_block(block: &[u8]) -> [u8; BLOCK_PIXELS] {
+  let palette = alpha_palette(block[0], block[1]);
+
+  let mut indices = [0u8; 8];
+  indices[..6].copy_from_slice(&block[2..8]);
+  let indices = u64::from_le_bytes(indices);
+
+  std::array::from_fn(|p| palette[((indices >> (3 * p)) & 0b111) as usize])
+}
+
+fn encode_alpha_block(pixels: &[Rgba; BLOCK_PIXELS]) -> [u8; 8] {
+  let alpha0 = pixels.iter().map(|pixel| pixel[3]).max().unwrap();
+  let alpha1 = pixels.iter().map(|pixel| pixel[3]).min().unwrap();
+
+  let palette = alpha_palette(alpha0, alpha1);
+  let mut indices = 0u64;
+  for (p, pixel) in pixels.iter().enumerate() {
+    let index = nearest(&palette, |alpha| (*alpha as i32 - pixel[3] as i32).abs());
+    indices |= (index as u64) << (3 * p);
+  }
+
+  let mut block = [0; 8];
+  block[0] = alpha0;
+  block[1] = alpha1;
+  block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+  block
+}
+
+fn nearest<T>(palette: &[T], distance: impl Fn(&T) -> i32) -> usize {
+  (0..palette.len())
+    .min_by_key(|&i| distance(&palette[i]))
+    .unwrap()
+}
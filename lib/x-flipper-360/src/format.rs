@@ -50,6 +50,16 @@ pub(crate) const fn get_dds_format(format: &Format) -> D3DFormat {
   }
 }
 
+pub(crate) const fn get_format(format: D3DFormat) -> Option<Format> {
+  match format {
+    D3DFormat::DXT1 => Some(Format::Dxt1),
+    D3DFormat::DXT3 => Some(Format::Dxt3),
+    D3DFormat::DXT5 => Some(Format::Dxt5),
+    D3DFormat::A8R8G8B8 => Some(Format::RGBA8),
+    _ => None,
+  }
+}
+
 pub(crate) const fn get_format_data(format: &Format) -> FormatData {
   match format {
     Format::Dxt1 => DXT1,
@@ -3,11 +3,12 @@ use std::io::{Read, Write};
 use dds::{D3DFormat, Dds, NewD3dParams};
 
 pub use crate::format::Format;
-use crate::format::{get_dds_format, get_format_data, FormatData};
+use crate::format::{get_dds_format, get_format, get_format_data, FormatData};
 use crate::mip_map::TextureInfo;
 pub use crate::texture_header::*;
 use crate::tile::{tile, untile};
 
+mod dxt;
 mod format;
 mod math;
 mod mip_map;
@@ -145,6 +146,73 @@ pub fn convert_from_dds<R: Read, W: Write>(
   Ok(output)
 }
 
+/// Uncompressed 8 bit RGBA pixels, row by row.
+#[derive(Debug, Clone)]
+pub struct Image {
+  pub width: u32,
+  pub height: u32,
+  pub data: Vec<u8>,
+}
+
+/// Decodes the top mip level of a dds, returning it with the dds format and mipmap level count.
+pub fn decode_dds<R: Read>(src: &mut R) -> Result<(Image, Format, u32), dds::Error> {
+  let dds = Dds::read(src)?;
+
+  let format = dds
+    .get_d3d_format()
+    .and_then(get_format)
+    .ok_or(dds::Error::UnsupportedFormat)?;
+
+  let image = Image {
+    width: dds.get_width(),
+    height: dds.get_height(),
+    data: dxt::decompress(&format, dds.get_width(), dds.get_height(), &dds.data),
+  };
+
+  Ok((image, format, dds.get_num_mipmap_levels()))
+}
+
+/// Encodes an image as dds, generating the lower mip levels from it.
+pub fn encode_dds<W: Write>(
+  image: &Image,
+  format: Format,
+  mipmap_levels: u32,
+  output: &mut W,
+) -> Result<(), dds::Error> {
+  if image.data.len() != (image.width * image.height * 4) as usize {
+    return Err(dds::Error::InvalidField(format!(
+      "image data has {} bytes, expected were {} for {}x{} pixels",
+      image.data.len(),
+      image.width * image.height * 4,
+      image.width,
+      image.height
+    )));
+  }
+
+  let mut dds = Dds::new_d3d(NewD3dParams {
+    format: get_dds_format(&format),
+    height: image.height,
+    width: image.width,
+    depth: None,
+    mipmap_levels: Some(mipmap_levels),
+    caps2: None,
+  })?;
+
+  let (mut width, mut height, mut pixels) = (image.width, image.height, image.data.clone());
+  let mut data = Vec::with_capacity(dds.data.len());
+
+  for mip in 0..dds.get_num_mipmap_levels() {
+    if mip > 0 {
+      (width, height, pixels) = dxt::downsample(width, height, &pixels);
+    }
+    data.extend(dxt::compress(&format, width, height, &pixels));
+  }
+
+  // ddsfile only estimates the size of non power of two mip chains, so keep the actual data
+  dds.data = data;
+  dds.write(output)
+}
+
 fn build_texture_info<'a>(config: &Config, format_data: &'a FormatData) -> TextureInfo<'a> {
   TextureInfo {
     width: config.width,
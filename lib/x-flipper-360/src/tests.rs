@@ -1,4 +1,6 @@
+use crate::format::get_dds_format;
 use crate::math::{align, log2_ceil, next_pow2};
+use crate::{decode_dds, encode_dds, Format, Image};
 
 #[test]
 fn test_align() {
@@ -32,3 +34,46 @@ fn test_next_pow2() {
   assert_eq!(next_pow2(6), 8);
   assert_eq!(next_pow2(7), 8);
 }
+
+#[test]
+fn test_dds_round_trip() {
+  // gradient with an odd size so the edge blocks are padded
+  let (width, height) = (13, 7);
+  let data = (0..width * height)
+    .flat_map(|i| {
+      let (x, y) = (i % width, i / width);
+      let value = ((x + y) * 12) as u8;
+      [value, 255 - value, 128, if x < 6 { 255 } else { 64 }]
+    })
+    .collect();
+  let image = Image {
+    width,
+    height,
+    data,
+  };
+
+  for (format, tolerance) in [
+    (Format::Dxt1, 255),
+    (Format::Dxt3, 24),
+    (Format::Dxt5, 24),
+    (Format::RGBA8, 0),
+  ] {
+    let mut dds = Vec::new();
+    encode_dds(&image, format, 3, &mut dds).unwrap();
+
+    let (decoded, decoded_format, mipmap_levels) = decode_dds(&mut dds.as_slice()).unwrap();
+    assert_eq!(get_dds_format(&decoded_format), get_dds_format(&format));
+    assert_eq!(mipmap_levels, 3);
+    assert_eq!((decoded.width, decoded.height), (width, height));
+
+    for (pixel, expected) in decoded.data.chunks(4).zip(image.data.chunks(4)) {
+      // dxt1 only keeps the color of opaque pixels
+      if expected[3] >= 128 || !matches!(format, Format::Dxt1) {
+        for c in 0..3 {
+          assert!(pixel[c].abs_diff(expected[c]) <= 24, "{format:?}");
+        }
+      }
+      assert!(pixel[3].abs_diff(expected[3]) <= tolerance, "{format:?}");
+    }
+  }
+}